@@ -0,0 +1,227 @@
+use crate::prelude::*;
+use crate::profile::Profile;
+use amethyst::config::Config;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+
+pub const POPUP_TIME: f32 = 4.;
+
+/// What a run has to do to earn an achievement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    ScoopsShoveled(u32),
+    DrillPulls(u32),
+    DrillsStarted(u32),
+    CaptchasSolved(u32),
+    BucketsEmptied(u32),
+    Depth(u32), // Meters.
+    DepthWithin { meters: u32, seconds: f32 },
+    DrillOnFirstPull, // Every drill start so far took a single pull.
+}
+
+impl Condition {
+    pub fn met(&self, digging: &DiggingStatus) -> bool {
+        match *self {
+            Condition::ScoopsShoveled(count) => digging.scoops_shoveled >= count,
+            Condition::DrillPulls(count) => digging.drill_pulls >= count,
+            Condition::DrillsStarted(count) => digging.drills_started >= count,
+            Condition::CaptchasSolved(count) => digging.captchas_solved >= count,
+            Condition::BucketsEmptied(count) => digging.buckets_emptied >= count,
+            Condition::Depth(meters) => digging.level() >= meters,
+            Condition::DepthWithin { meters, seconds } => {
+                digging.level() >= meters && digging.time_played <= seconds
+            }
+            Condition::DrillOnFirstPull => {
+                digging.drills_started > 0 && digging.drill_pulls == digging.drills_started
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Achievement {
+    pub id: String, // What the profile remembers it by, so names can be reworded.
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+/// Every achievement there is, loaded from `config/achievements.ron`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Achievements {
+    pub list: Vec<Achievement>,
+}
+
+// The same list as config/achievements.ron, kept in step by default_matches_bundled_config.
+impl Default for Achievements {
+    fn default() -> Self {
+        let achievement = |id: &str, name: &str, description: &str, condition| Achievement {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            condition,
+        };
+        Achievements {
+            list: vec![
+                achievement(
+                    "first_scoop",
+                    "Breaking Ground",
+                    "Shovel your first scoop",
+                    Condition::ScoopsShoveled(1),
+                ),
+                achievement(
+                    "shovel_500",
+                    "Blisters",
+                    "Shovel 500 scoops",
+                    Condition::ScoopsShoveled(500),
+                ),
+                achievement(
+                    "buckets_100",
+                    "Bucket Brigade",
+                    "Empty 100 buckets",
+                    Condition::BucketsEmptied(100),
+                ),
+                achievement(
+                    "drill_first_pull",
+                    "Purring Along",
+                    "Start the drill on the first pull",
+                    Condition::DrillOnFirstPull,
+                ),
+                achievement(
+                    "drills_10",
+                    "Heavy Machinery",
+                    "Start the drill 10 times",
+                    Condition::DrillsStarted(10),
+                ),
+                achievement(
+                    "captchas_10",
+                    "Not A Robot",
+                    "Solve 10 captchas",
+                    Condition::CaptchasSolved(10),
+                ),
+                achievement(
+                    "fast_40",
+                    "In A Hurry",
+                    "Reach 40m in under 5 minutes",
+                    Condition::DepthWithin {
+                        meters: 40,
+                        seconds: 300.,
+                    },
+                ),
+                achievement(
+                    "depth_100",
+                    "Deep Enough",
+                    "Reach 100m",
+                    Condition::Depth(100),
+                ),
+            ],
+        }
+    }
+}
+
+impl Achievements {
+    pub fn load(path: &Path) -> Achievements {
+        <Achievements as Config>::load(path)
+            .map_err(|err| warn!("Could not load achievements {:?}: {}", path, err))
+            .unwrap_or_default()
+    }
+}
+
+/// Checks every achievement not yet earned against the run, banks new ones in the profile, and
+/// announces them one popup at a time.
+#[derive(Default)]
+pub struct AchievementSystem {
+    queue: VecDeque<usize>,
+    popup: Option<(Entity, usize, f32)>, // The popup, which achievement it shows, time left.
+}
+
+impl<'s> System<'s> for AchievementSystem {
+    type SystemData = (
+        Read<'s, DiggingStatus>,
+        Read<'s, Achievements>,
+        Write<'s, Profile>,
+        WidgetSpawner<'s>,
+        WriteStorage<'s, UiText>,
+        UiFinder<'s>,
+        SoundPlayer<'s>,
+        Entities<'s>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            digging,
+            achievements,
+            mut profile,
+            mut spawner,
+            mut texts,
+            finder,
+            sounds,
+            entities,
+            time,
+        ): Self::SystemData,
+    ) {
+        let mut earned = false;
+        for (index, achievement) in achievements.list.iter().enumerate() {
+            if !profile.achievements.contains(&achievement.id)
+                && achievement.condition.met(&digging)
+            {
+                info!("Achievement earned: {}", achievement.name);
+                profile.achievements.push(achievement.id.clone());
+                self.queue.push_back(index);
+                earned = true;
+            }
+        }
+        if earned {
+            profile.store();
+        }
+
+        if let Some((entity, index, time_left)) = self.popup.as_mut() {
+            *time_left -= time.delta_seconds();
+            let achievement = &achievements.list[*index];
+            if let Some(text) = finder
+                .find("achievement_name")
+                .and_then(|ent| texts.get_mut(ent))
+            {
+                text.text = achievement.name.clone();
+            }
+            if let Some(text) = finder
+                .find("achievement_description")
+                .and_then(|ent| texts.get_mut(ent))
+            {
+                text.text = achievement.description.clone();
+            }
+            if *time_left <= 0. || !entities.is_alive(*entity) {
+                if entities.is_alive(*entity) {
+                    entities
+                        .delete(*entity)
+                        .expect("Unreachable, entity is alive");
+                }
+                self.popup = None;
+            }
+        }
+        if self.popup.is_none() {
+            if let Some(index) = self.queue.pop_front() {
+                sounds.play(Sound::RobotCaptchaSuccess);
+                let entity = spawner
+                    .spawn_ui_widget("prefabs/achievement_popup.ron", Position { x: 0., y: -64. });
+                self.popup = Some((entity, index, POPUP_TIME));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_bundled_config() {
+        let bundled = <Achievements as Config>::load(Path::new("config/achievements.ron"))
+            .expect("config/achievements.ron should load");
+        assert_eq!(bundled, Achievements::default());
+    }
+}
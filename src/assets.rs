@@ -1,4 +1,3 @@
-use crate::captcha::CaptchaData;
 use crate::prelude::*;
 pub use amethyst::assets::{
     AssetStorage, Handle, Loader, Prefab, PrefabData, PrefabLoader, ProgressCounter, RonFormat,
@@ -75,6 +74,8 @@ pub struct SoundStorage {
     pub robot_captcha_success: SourceHandle,
     pub robot_captcha_key: SourceHandle,
     pub robot_unlock: SourceHandle,
+    pub pickaxe_hit: SourceHandle,
+    pub pickaxe_break: SourceHandle,
 }
 
-pub type GameAssets = (SpriteStorage, SoundStorage, Vec<CaptchaData>);
+pub type GameAssets = (SpriteStorage, SoundStorage);
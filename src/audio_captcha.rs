@@ -0,0 +1,169 @@
+use amethyst::utils::application_root_dir;
+use log::warn;
+use rand::Rng;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Silence before, between and after the characters, in seconds.
+const CHARACTER_GAP: f32 = 0.5;
+// Between "capital" and the letter it goes with.
+const CAPITAL_GAP: f32 = 0.1;
+// Loudest hiss, at a captcha tier's full noise, as a fraction of full scale.
+const NOISE_LEVEL: f32 = 0.25;
+
+/// A clip of each character an audio captcha can say, from `assets/audio/captcha/<character>.wav`,
+/// and of the word "capital" from `capital.wav`.
+///
+/// Clips must be 16-bit mono PCM, all at one sample rate. Letters are looked up lowercase, so one
+/// clip covers both cases, with "capital" said before upper case ones when the case matters. The
+/// clips that ship are synthesized speech; recordings of someone reading the characters out can
+/// replace them under the same names.
+#[derive(Default)]
+pub struct ClipBank {
+    sample_rate: u32,
+    clips: HashMap<char, Vec<i16>>,
+    capital: Option<Vec<i16>>,
+}
+
+pub fn clips_path() -> PathBuf {
+    application_root_dir()
+        .map(|root| root.join("assets/audio/captcha"))
+        .unwrap_or_else(|_| PathBuf::from("assets/audio/captcha"))
+}
+
+impl ClipBank {
+    pub fn load(directory: &Path) -> ClipBank {
+        let mut bank = ClipBank::default();
+        let entries = match std::fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("No audio captcha clips in {:?}: {}", directory, err);
+                return bank;
+            }
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("");
+            let mut chars = stem.chars();
+            // None for the "capital" clip.
+            let character = match (chars.next(), chars.next()) {
+                (Some(character), None) => Some(character.to_ascii_lowercase()),
+                _ if stem == "capital" => None,
+                _ => continue,
+            };
+            let first = bank.clips.is_empty() && bank.capital.is_none();
+            match std::fs::read(&path).ok().and_then(|bytes| read_wav(&bytes)) {
+                Some((rate, samples)) if first || rate == bank.sample_rate => {
+                    bank.sample_rate = rate;
+                    match character {
+                        Some(character) => bank.clips.insert(character, samples),
+                        None => bank.capital.replace(samples),
+                    };
+                }
+                Some((rate, _)) => warn!(
+                    "Skipping {:?}: it's {}Hz, where the other clips are {}Hz",
+                    path, rate, bank.sample_rate
+                ),
+                None => warn!("Skipping {:?}: not a 16-bit mono PCM WAV file", path),
+            }
+        }
+        bank
+    }
+
+    /// Says `answer` one character at a time with hiss mixed in, `noise` being a captcha tier's
+    /// (0 to 1), as a WAV file. With `say_case`, upper case letters are said as "capital A".
+    /// None if any character, or "capital" when it's needed, has no clip.
+    pub fn synthesize(
+        &self,
+        answer: &str,
+        noise: f32,
+        say_case: bool,
+        rng: &mut impl Rng,
+    ) -> Option<Vec<u8>> {
+        let gap = vec![0; (self.sample_rate as f32 * CHARACTER_GAP) as usize];
+        let capital_gap = vec![0; (self.sample_rate as f32 * CAPITAL_GAP) as usize];
+        let mut samples = gap.clone();
+        for character in answer.chars() {
+            if say_case && character.is_ascii_uppercase() {
+                samples.extend_from_slice(self.capital.as_ref()?);
+                samples.extend_from_slice(&capital_gap);
+            }
+            samples.extend_from_slice(self.clips.get(&character.to_ascii_lowercase())?);
+            samples.extend_from_slice(&gap);
+        }
+        let hiss = noise.max(0.).min(1.) * NOISE_LEVEL * i16::MAX as f32;
+        for sample in samples.iter_mut() {
+            let noisy = *sample as f32 + rng.gen_range(-1f32..=1.) * hiss;
+            *sample = noisy.max(i16::MIN as f32).min(i16::MAX as f32) as i16;
+        }
+        Some(write_wav(self.sample_rate, &samples))
+    }
+}
+
+/// The sample rate and samples of a 16-bit mono PCM WAV file.
+fn read_wav(bytes: &[u8]) -> Option<(u32, Vec<i16>)> {
+    let u16_at = |at: usize| {
+        bytes
+            .get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let u32_at = |at: usize| {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let mut sample_rate = None;
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let size = u32_at(at + 4)? as usize;
+        let body = at + 8;
+        match bytes.get(at..at + 4)? {
+            b"fmt " => {
+                let (format, channels, bits) =
+                    (u16_at(body)?, u16_at(body + 2)?, u16_at(body + 14)?);
+                if (format, channels, bits) != (1, 1, 16) {
+                    return None;
+                }
+                sample_rate = u32_at(body + 4);
+            }
+            b"data" => {
+                let data = bytes.get(body..(body + size).min(bytes.len()))?;
+                let samples = data
+                    .chunks_exact(2)
+                    .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                return Some((sample_rate?, samples));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length.
+        at = body + size + size % 2;
+    }
+    None
+}
+
+fn write_wav(sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+    let data_size = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_size as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM.
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // Mono.
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // Bytes per second.
+    bytes.extend_from_slice(&2u16.to_le_bytes()); // Bytes per sample.
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
@@ -0,0 +1,548 @@
+use crate::cards::{
+    AlertState, BucketAlertState, ChallengeKind, DrillAlertState, PickaxeAlertState,
+    RobotAlertState, ShovelAlertState, ToolId,
+};
+use crate::prelude::{Position, Sound};
+use crate::strata::Strata;
+use amethyst::config::Config;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// GameplayState spawns this many bucket widgets, so there can't be more buckets than this.
+pub const MAX_BUCKETS: u32 = 16;
+pub const BALANCE_CHECK_INTERVAL: f32 = 1.;
+
+/// A tool that becomes available once the dig passes `meter`, with the alertable that opens it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Unlock {
+    pub meter: u32,
+    pub tool: ToolId,
+    pub prefab: String,
+    pub sound: Option<Sound>,
+    pub position: Position,
+    pub state: AlertState,
+}
+
+/// How hard the captchas get once the dig passes `meter`, which other puzzles robots may ask for
+/// instead, and how much longer a robot runs for solving one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptchaTier {
+    pub meter: u32,
+    pub chars: u32,
+    pub noise: f32,            // Chance of any one pixel being speckled.
+    pub dots: u32,             // Blots scattered over the text.
+    pub wave: (f64, f64),      // Frequency and amplitude of the warp.
+    pub color: [u8; 3],        // Of the text.
+    pub robot_time_scale: f32, // Multiplies robot_time.
+    #[serde(default)]
+    pub challenges: Vec<ChallengeKind>, // Robots pick one at random. Just captchas if empty.
+}
+
+/// How a robot checks the answer it's given against the right one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptchaValidation {
+    pub case_insensitive: bool,
+    pub confusables: Vec<String>, // Groups of characters that pass for each other, like "0Oo".
+    pub partial_credit: f32, // Fraction of the robot's time one wrong character still earns. 0 is off.
+}
+
+impl Default for CaptchaValidation {
+    fn default() -> Self {
+        CaptchaValidation {
+            case_insensitive: true,
+            confusables: ["0Oo", "1lIi", "5Ss", "2Zz"]
+                .iter()
+                .map(|group| group.to_string())
+                .collect(),
+            partial_credit: 0.5,
+        }
+    }
+}
+
+/// What a robot made of an answer.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CaptchaVerdict {
+    Correct,
+    Partial, // One character off, which still gets the robot going for less time.
+    Wrong,
+}
+
+impl CaptchaValidation {
+    /// Takes only the right answer, give or take surrounding spaces.
+    pub fn exact() -> Self {
+        CaptchaValidation {
+            case_insensitive: false,
+            confusables: Vec::new(),
+            partial_credit: 0.,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0. ..=1.).contains(&self.partial_credit) {
+            return Err("captcha_validation's partial_credit must be between 0 and 1".to_string());
+        }
+        // A character in two groups would count as whichever group happens to come first.
+        let fold = |c: char| {
+            if self.case_insensitive {
+                c.to_ascii_lowercase()
+            } else {
+                c
+            }
+        };
+        for (index, group) in self.confusables.iter().enumerate() {
+            for c in group.chars().map(fold) {
+                let other = self.confusables[index + 1..]
+                    .iter()
+                    .find(|other| other.chars().map(fold).any(|other| other == c));
+                if let Some(other) = other {
+                    return Err(format!(
+                        "captcha_validation's confusables {:?} and {:?} both have {:?}",
+                        group, other, c
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The character `c` counts as, once case and confusable glyphs are folded away.
+    fn canonical(&self, c: char) -> char {
+        let same =
+            |other: char| other == c || (self.case_insensitive && other.eq_ignore_ascii_case(&c));
+        let group = self
+            .confusables
+            .iter()
+            .find(|group| group.chars().any(same));
+        match group.and_then(|group| group.chars().next()) {
+            Some(first) => first,
+            None if self.case_insensitive => c.to_ascii_lowercase(),
+            None => c,
+        }
+    }
+
+    pub fn check(&self, answer: &str, typed: &str) -> CaptchaVerdict {
+        let fold =
+            |text: &str| -> Vec<char> { text.trim().chars().map(|c| self.canonical(c)).collect() };
+        let (answer, typed) = (fold(answer), fold(typed));
+        if typed == answer {
+            CaptchaVerdict::Correct
+        } else if self.partial_credit > 0.
+            && typed.len() == answer.len()
+            && typed
+                .iter()
+                .zip(answer.iter())
+                .filter(|(a, b)| a != b)
+                .count()
+                == 1
+        {
+            CaptchaVerdict::Partial
+        } else {
+            CaptchaVerdict::Wrong
+        }
+    }
+}
+
+/// Every number the designers tune, loaded from `config/balance.ron`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Balance {
+    pub scoops_per_block: u32,
+    pub scoops_per_meter: u32,
+    pub drill_time: f32,
+    pub drill_speed: f32,
+    pub robot_time: f32,
+    pub robot_speed: f32,
+    pub victory_depth: u32,
+    pub bucket_success_time: f32,
+    pub starting_buckets: u32,
+    pub scoops_per_bucket: u32,
+    pub pickaxe_hits: u32,
+    pub upgrade_cost: u32,
+    pub upgrade_cost_growth: f32,
+    pub bucket_size_step: u32,
+    pub drill_time_step: f32,
+    pub robot_speed_step: f32,
+    pub prestige_per_run: u32,
+    pub prestige_par_time: f32,
+    pub prestige_per_captcha: f32,
+    pub prestige_per_drill: f32,
+    pub prestige_cost: u32,
+    pub prestige_cost_growth: f32,
+    pub prestige_robot_speed: f32,
+    pub unlocks: Vec<Unlock>,
+    pub captcha_tiers: Vec<CaptchaTier>, // Shallowest first, the first at 0m.
+    pub captcha_validation: CaptchaValidation,
+    // Loaded from strata.ron next to the balance file, but kept here so replays carry it too.
+    pub strata: Strata,
+}
+
+// The same numbers as config/balance.ron, kept in step by default_matches_bundled_config.
+impl Default for Balance {
+    fn default() -> Self {
+        Balance {
+            scoops_per_block: 4,
+            scoops_per_meter: 28,
+            drill_time: 60.,
+            drill_speed: 4.,
+            robot_time: 60.,
+            robot_speed: 0.25,
+            victory_depth: 100,
+            bucket_success_time: 1.,
+            starting_buckets: 5,
+            scoops_per_bucket: 8,
+            pickaxe_hits: 3,
+            upgrade_cost: 40,
+            upgrade_cost_growth: 1.5,
+            bucket_size_step: 2,
+            drill_time_step: 15.,
+            robot_speed_step: 0.05,
+            prestige_per_run: 10,
+            prestige_par_time: 1800.,
+            prestige_per_captcha: 0.5,
+            prestige_per_drill: 0.25,
+            prestige_cost: 5,
+            prestige_cost_growth: 2.,
+            prestige_robot_speed: 0.1,
+            unlocks: vec![
+                Unlock {
+                    meter: 0,
+                    tool: ToolId::Shovel,
+                    prefab: "prefabs/shovel_alertable.ron".to_string(),
+                    sound: None,
+                    position: Position { x: -64., y: -32. },
+                    state: AlertState::Shovel(ShovelAlertState::Ready),
+                },
+                Unlock {
+                    meter: 0,
+                    tool: ToolId::Bucket,
+                    prefab: "prefabs/bucket_alertable.ron".to_string(),
+                    sound: None,
+                    position: Position { x: -64., y: -96. },
+                    state: AlertState::Bucket(BucketAlertState::Empty),
+                },
+                Unlock {
+                    meter: 1,
+                    tool: ToolId::Pickaxe,
+                    prefab: "prefabs/pickaxe_alertable.ron".to_string(),
+                    sound: Some(Sound::PickaxeBreak),
+                    position: Position { x: -64., y: -480. },
+                    state: AlertState::Pickaxe(PickaxeAlertState::Waiting),
+                },
+                Unlock {
+                    meter: 20,
+                    tool: ToolId::Drill,
+                    prefab: "prefabs/drill_alertable.ron".to_string(),
+                    sound: Some(Sound::DrillUnlock),
+                    position: Position { x: -64., y: -160. },
+                    state: AlertState::Drill(DrillAlertState::Ready),
+                },
+                Unlock {
+                    meter: 40,
+                    tool: ToolId::Robot,
+                    prefab: "prefabs/robot_alertable.ron".to_string(),
+                    sound: Some(Sound::RobotUnlock),
+                    position: Position { x: -64., y: -224. },
+                    state: AlertState::Robot(0, RobotAlertState::CaptchaNeeded),
+                },
+                Unlock {
+                    meter: 60,
+                    tool: ToolId::Robot,
+                    prefab: "prefabs/robot_alertable.ron".to_string(),
+                    sound: Some(Sound::RobotUnlock),
+                    position: Position { x: -64., y: -352. },
+                    state: AlertState::Robot(1, RobotAlertState::CaptchaNeeded),
+                },
+                Unlock {
+                    meter: 80,
+                    tool: ToolId::Robot,
+                    prefab: "prefabs/robot_alertable.ron".to_string(),
+                    sound: Some(Sound::RobotUnlock),
+                    position: Position { x: -64., y: -416. },
+                    state: AlertState::Robot(2, RobotAlertState::CaptchaNeeded),
+                },
+                Unlock {
+                    meter: 0,
+                    tool: ToolId::Shop,
+                    prefab: "prefabs/shop_alertable.ron".to_string(),
+                    sound: None,
+                    position: Position { x: -64., y: -288. },
+                    state: AlertState::Shop,
+                },
+            ],
+            captcha_tiers: vec![
+                CaptchaTier {
+                    meter: 0,
+                    chars: 5,
+                    noise: 0.3,
+                    dots: 3,
+                    wave: (0.3, 0.6),
+                    color: [22, 50, 166],
+                    robot_time_scale: 1.,
+                    challenges: vec![
+                        ChallengeKind::Captcha,
+                        ChallengeKind::Arithmetic,
+                        ChallengeKind::Memory,
+                    ],
+                },
+                CaptchaTier {
+                    meter: 60,
+                    chars: 6,
+                    noise: 0.4,
+                    dots: 5,
+                    wave: (0.4, 0.8),
+                    color: [90, 40, 140],
+                    robot_time_scale: 1.5,
+                    challenges: vec![
+                        ChallengeKind::Captcha,
+                        ChallengeKind::Arithmetic,
+                        ChallengeKind::Memory,
+                        ChallengeKind::DirtTiles,
+                    ],
+                },
+                CaptchaTier {
+                    meter: 80,
+                    chars: 7,
+                    noise: 0.5,
+                    dots: 7,
+                    wave: (0.5, 1.),
+                    color: [140, 30, 30],
+                    robot_time_scale: 2.,
+                    challenges: vec![
+                        ChallengeKind::Captcha,
+                        ChallengeKind::Memory,
+                        ChallengeKind::DirtTiles,
+                        ChallengeKind::Slider,
+                    ],
+                },
+            ],
+            captcha_validation: CaptchaValidation::default(),
+            strata: Strata::default(),
+        }
+    }
+}
+
+impl Balance {
+    pub fn load(path: &Path) -> Result<Balance, String> {
+        let mut balance = <Balance as Config>::load(path).map_err(|err| err.to_string())?;
+        balance.strata = Strata::load(&strata_path(path))?;
+        balance.validate()?;
+        Ok(balance)
+    }
+
+    pub fn blocks_per_meter(&self) -> u32 {
+        self.scoops_per_meter / self.scoops_per_block
+    }
+
+    pub fn unlock_meter(&self, tool: ToolId) -> Option<u32> {
+        self.unlocks
+            .iter()
+            .find(|unlock| unlock.tool == tool)
+            .map(|unlock| unlock.meter)
+    }
+
+    /// Which of the captcha tiers applies at `meter`.
+    pub fn captcha_tier_at(&self, meter: u32) -> usize {
+        self.captcha_tiers
+            .iter()
+            .rposition(|tier| tier.meter <= meter)
+            .unwrap_or(0)
+    }
+
+    pub fn robot_count(&self) -> usize {
+        self.unlocks
+            .iter()
+            .filter(|unlock| unlock.tool == ToolId::Robot)
+            .count()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.scoops_per_block == 0 {
+            return Err("scoops_per_block must be at least 1".to_string());
+        }
+        if self.scoops_per_meter % self.scoops_per_block != 0 {
+            return Err(format!(
+                "scoops_per_meter ({}) must be divisible by scoops_per_block ({})",
+                self.scoops_per_meter, self.scoops_per_block
+            ));
+        }
+        if self.blocks_per_meter() < 2 {
+            return Err("scoops_per_meter must cover at least two blocks".to_string());
+        }
+        let mut robots = 0;
+        for unlock in self.unlocks.iter() {
+            if unlock.tool == ToolId::Robot {
+                match unlock.state {
+                    AlertState::Robot(robot, _) if robot == robots => robots += 1,
+                    _ => {
+                        return Err(format!(
+                            "Robot unlock {} must have the state Robot({}, ...)",
+                            robots, robots
+                        ))
+                    }
+                }
+            }
+            if unlock.meter >= self.victory_depth {
+                return Err(format!(
+                    "The {:?} unlock at {}m must be shallower than victory_depth ({})",
+                    unlock.tool, unlock.meter, self.victory_depth
+                ));
+            }
+        }
+        if self.starting_buckets == 0 || self.starting_buckets > MAX_BUCKETS {
+            return Err(format!(
+                "starting_buckets must be between 1 and {}",
+                MAX_BUCKETS
+            ));
+        }
+        if self.scoops_per_bucket == 0 {
+            return Err("scoops_per_bucket must be at least 1".to_string());
+        }
+        if self.pickaxe_hits == 0 {
+            return Err("pickaxe_hits must be at least 1".to_string());
+        }
+        if self.upgrade_cost == 0 || self.upgrade_cost_growth < 1. {
+            return Err("upgrades must cost something, and never get cheaper".to_string());
+        }
+        if self.prestige_cost == 0 || self.prestige_cost_growth < 1. {
+            return Err("prestige bonuses must cost something, and never get cheaper".to_string());
+        }
+        if !(self.prestige_par_time > 0.) {
+            return Err("prestige_par_time must be positive".to_string());
+        }
+        match self.captcha_tiers.first() {
+            Some(tier) if tier.meter == 0 => {}
+            _ => return Err("The first captcha tier must start at 0m".to_string()),
+        }
+        let mut meter = 0;
+        for tier in self.captcha_tiers.iter() {
+            if tier.meter < meter {
+                return Err(format!(
+                    "The captcha tier at {}m must come after the one at {}m",
+                    tier.meter, meter
+                ));
+            }
+            if tier.chars == 0 || !(tier.robot_time_scale > 0.) {
+                return Err(format!(
+                    "The captcha tier at {}m needs at least one character and a positive robot_time_scale",
+                    tier.meter
+                ));
+            }
+            if !(0. ..=1.).contains(&tier.noise) {
+                return Err(format!(
+                    "The captcha tier at {}m must have a noise between 0 and 1",
+                    tier.meter
+                ));
+            }
+            meter = tier.meter;
+        }
+        self.captcha_validation.validate()?;
+        self.strata.validate(self.victory_depth)?;
+        let timings = [
+            ("drill_time", self.drill_time),
+            ("drill_speed", self.drill_speed),
+            ("robot_time", self.robot_time),
+            ("robot_speed", self.robot_speed),
+            ("bucket_success_time", self.bucket_success_time),
+        ];
+        for (name, value) in timings.iter() {
+            if !(*value > 0.) {
+                return Err(format!("{} must be positive, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a running game can take this balance, or needs a new run to rebuild the shaft.
+    pub fn fits_shaft_of(&self, current: &Balance) -> bool {
+        self.scoops_per_block == current.scoops_per_block
+            && self.scoops_per_meter == current.scoops_per_meter
+            && self.victory_depth == current.victory_depth
+    }
+}
+
+/// Watches the balance and strata files, so designers can tune while the game runs.
+pub struct BalanceWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    since_check: f32,
+}
+
+impl BalanceWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        BalanceWatcher {
+            path,
+            modified,
+            since_check: 0.,
+        }
+    }
+
+    /// Returns a freshly loaded balance if the file has changed and the change is usable.
+    pub fn poll(&mut self, dt: f32, current: &Balance) -> Option<Balance> {
+        self.since_check += dt;
+        if self.since_check < BALANCE_CHECK_INTERVAL {
+            return None;
+        }
+        self.since_check = 0.;
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        match Balance::load(&self.path) {
+            Ok(balance) if !balance.fits_shaft_of(current) => {
+                warn!(
+                    "Not reloading {:?}: changing the shaft's scoops or depth needs a restart",
+                    self.path
+                );
+                None
+            }
+            Ok(balance) => {
+                // A run copies its buckets when it starts, so the one in progress keeps its own.
+                if balance.starting_buckets != current.starting_buckets
+                    || balance.scoops_per_bucket != current.scoops_per_bucket
+                {
+                    info!(
+                        "Reloaded {:?}; starting_buckets and scoops_per_bucket apply from the next run",
+                        self.path
+                    );
+                } else {
+                    info!("Reloaded {:?}", self.path);
+                }
+                Some(balance)
+            }
+            Err(err) => {
+                warn!("Not reloading {:?}: {}", self.path, err);
+                None
+            }
+        }
+    }
+}
+
+pub fn strata_path(balance_path: &Path) -> PathBuf {
+    balance_path.with_file_name("strata.ron")
+}
+
+/// The later of the balance and strata files' modification times, so editing either reloads.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    let modified = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    modified(path).max(modified(&strata_path(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_bundled_config() {
+        let bundled = Balance::load(Path::new("config/balance.ron"));
+        assert_eq!(bundled, Ok(Balance::default()));
+    }
+}
@@ -1,76 +1,168 @@
-use crate::assets::load_texture;
+use crate::audio_captcha::{clips_path, ClipBank};
+use crate::balance::CaptchaTier;
 use crate::prelude::*;
 use amethyst::{
-    assets::{
-        AssetStorage, Handle, Loader, Prefab, PrefabData, PrefabLoader, ProgressCounter, RonFormat,
-    },
+    assets::{AssetStorage, Handle, Loader},
+    audio::AudioData,
     renderer::{
         rendy::{
             hal::{
                 format::Format,
-                image::{Anisotropic, Filter, Kind, PackedColor, SamplerInfo, ViewKind, WrapMode},
+                image::{Kind, ViewKind},
             },
             texture::TextureBuilder,
         },
         types::TextureData,
-        // ImageFormat,
     },
 };
 use captcha::{filters, Captcha};
 use image::{self, ImageFormat};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
 
-pub const CAPTCHA_COUNT: u32 = 5;
+// Characters answers are drawn from. Ones that are hard to tell apart in the distorted font are
+// left out.
+pub const CAPTCHA_CHARS: &str = "abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
 pub const CAPTCHA_WIDTH: u32 = 220;
 pub const CAPTCHA_HEIGHT: u32 = 120;
 
+/// A captcha to draw: its answer, and the noise for its audio, come from the run's seed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptchaRequest {
+    pub answer: String,
+    pub tier: usize,        // Which of the balance's captcha tiers it's for.
+    pub style: CaptchaTier, // That tier, as the worker needs it.
+    pub audio_seed: u64,
+    pub say_case: bool, // Whether the audio says which letters are capitals.
+}
+
+impl CaptchaRequest {
+    /// Draws a new answer of the tier's length, and anything else random about it, from `rng`.
+    pub fn draw(tier: usize, style: &CaptchaTier, say_case: bool, rng: &mut StdRng) -> Self {
+        let chars: Vec<char> = CAPTCHA_CHARS.chars().collect();
+        let answer = (0..style.chars)
+            .map(|_| chars[rng.gen_range(0..chars.len())])
+            .collect();
+        CaptchaRequest {
+            answer,
+            tier,
+            style: style.clone(),
+            audio_seed: rng.gen(),
+            say_case,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct CaptchaData {
     pub answer: String,
     pub texture: Handle<Texture>,
+    pub audio: Option<SourceHandle>, // The answer read out, for players who can't see the image.
+}
+
+/// A captcha drawn on the worker thread, not yet a texture or a sound.
+struct CaptchaImage {
+    answer: String,
+    rgba: Vec<u8>,
+    audio: Option<Vec<u8>>, // A WAV file.
+}
+
+/// The captcha the open card is waiting for, drawn on a worker thread so a new captcha never
+/// stalls a frame. Only the one the open card still asks for is kept: a card closed before its
+/// captcha arrived leaves nothing behind.
+///
+/// What a captcha says is decided by the card, from the run's seed, so seeded runs and replays
+/// see the same captchas. Only the distortion is the captcha crate's own.
+pub struct CaptchaPool {
+    wanted: Option<String>, // The answer a card asked for since the pool system last ran.
+    requested: Option<String>, // The answer the worker was last asked to draw.
+    ready: Option<CaptchaData>,
+    requests: Mutex<Sender<CaptchaRequest>>,
+    incoming: Mutex<Receiver<CaptchaImage>>,
 }
 
-pub fn get_captchas<'a>(world: &mut World, progress: &'a mut ProgressCounter) -> Vec<CaptchaData> {
-    let mut captchas = Vec::new();
-    for _n in 1..=CAPTCHA_COUNT {
-        captchas.push(gen_captcha(world, progress));
+impl CaptchaPool {
+    /// Starts the worker thread. It stops on its own once the pool is dropped.
+    pub fn start() -> Self {
+        let (requests, worker_requests) = channel::<CaptchaRequest>();
+        let (sender, incoming) = channel();
+        thread::Builder::new()
+            .name("captchas".to_string())
+            .spawn(move || {
+                let clips = ClipBank::load(&clips_path());
+                for request in worker_requests {
+                    if sender.send(gen_captcha(request, &clips)).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("failed to start the captcha thread");
+        CaptchaPool {
+            wanted: None,
+            requested: None,
+            ready: None,
+            requests: Mutex::new(requests),
+            incoming: Mutex::new(incoming),
+        }
+    }
+
+    /// The drawn captcha for `request`, handed over once. The first call asks the worker for it,
+    /// and it's None until the worker is done, a frame or two later. A card waiting on its
+    /// captcha has to keep asking every frame, or the pool takes it as closed.
+    pub fn fetch(&mut self, request: &CaptchaRequest) -> Option<CaptchaData> {
+        self.wanted = Some(request.answer.clone());
+        match self.ready.take() {
+            Some(captcha) if captcha.answer == request.answer => return Some(captcha),
+            _ => {}
+        }
+        if self.requested.as_ref() != Some(&request.answer) {
+            self.requested = Some(request.answer.clone());
+            // The worker only stops by panicking, which already says why.
+            let _ = self
+                .requests
+                .lock()
+                .expect("Unreachable: nothing panics holding the lock")
+                .send(request.clone());
+        }
+        None
     }
-    captchas
 }
 
-fn gen_captcha<'a>(world: &mut World, progress: &'a mut ProgressCounter) -> CaptchaData {
-    let (answer, image_png_buffer) = Captcha::new()
-        .add_chars(5)
-        .apply_filter(filters::Noise::new(0.3))
-        .apply_filter(filters::Dots::new(3))
+fn gen_captcha(request: CaptchaRequest, clips: &ClipBank) -> CaptchaImage {
+    let style = &request.style;
+    let mut captcha = Captcha::new();
+    for character in request.answer.chars() {
+        // With only one character to pick from, the crate draws exactly the one asked for.
+        captcha.set_chars(&[character]).add_char();
+    }
+    let (_, image_png_buffer) = captcha
+        .apply_filter(filters::Noise::new(style.noise))
+        .apply_filter(filters::Dots::new(style.dots))
         .apply_filter(filters::Cow::new())
-        .apply_filter(filters::Wave::new(0.3, 0.6))
-        .set_color([22, 50, 166])
+        .apply_filter(filters::Wave::new(style.wave.0, style.wave.1))
+        .set_color(style.color)
         .view(CAPTCHA_WIDTH, CAPTCHA_HEIGHT)
         .as_tuple()
         .expect("failed to gen captcha");
-    let image =
-        image::load_from_memory_with_format(&image_png_buffer, ImageFormat::Png).expect("image???");
-    // let texture = load_2d_texture(
-    //     world,
-    //     progress,
-    //     image.into_rgb8().into_vec(),
-    //     CAPTCHA_WIDTH,
-    //     CAPTCHA_HEIGHT,
-    // );
-    let file_path = format!("generated/{}.png", answer);
-    image.save(format!("assets/{}", file_path));
-    let texture = load_texture(world, file_path, progress);
-    CaptchaData { answer, texture }
+    let image = image::load_from_memory_with_format(&image_png_buffer, ImageFormat::Png)
+        .expect("Unreachable: the captcha crate always encodes a valid PNG");
+    let mut rng = StdRng::seed_from_u64(request.audio_seed);
+    let audio = clips.synthesize(&request.answer, style.noise, request.say_case, &mut rng);
+    CaptchaImage {
+        answer: request.answer,
+        rgba: image.into_rgba8().into_vec(),
+        audio,
+    }
 }
 
-// file:///home/basicdays/wip/games/LudumDare48/target/doc/amethyst_rendy/struct.ImageFormat.html
-// https://community.amethyst.rs/t/how-to-load-a-texture-from-memory/1090
-// https://stackoverflow.com/questions/57691913/how-to-load-a-texture-from-memory-in-amethyst-engine
-
-fn load_2d_texture<'a>(
-    world: &mut World,
-    progress: &'a mut ProgressCounter,
-    image_buffer: Vec<u8>,
+/// Uploads raw RGBA pixels as a texture, so generated images never have to touch the disk.
+fn load_2d_texture(
+    loader: &Loader,
+    texture_storage: &AssetStorage<Texture>,
+    rgba: Vec<u8>,
     width: u32,
     height: u32,
 ) -> Handle<Texture> {
@@ -79,25 +171,54 @@ fn load_2d_texture<'a>(
         .with_data_height(height)
         .with_kind(Kind::D2(width, height, 1, 1))
         .with_view_kind(ViewKind::D2)
-        // TextureBuilder::new already has this...
-        // .with_sampler_info(SamplerInfo {
-        //     min_filter: Filter::Linear,
-        //     mag_filter: Filter::Linear,
-        //     mip_filter: Filter::Linear,
-        //     wrap_mode: (WrapMode::Clamp, WrapMode::Clamp, WrapMode::Clamp),
-        //     lod_bias: 0.0.into(),
-        //     lod_range: std::ops::Range {
-        //         start: 0.0.into(),
-        //         end: 1000.0.into(),
-        //     },
-        //     comparison: None,
-        //     border: PackedColor(0),
-        //     anisotropic: Anisotropic::Off,
-        //     normalized: false,
-        // })
-        .with_raw_data(image_buffer, Format::Rgb8Uint);
+        .with_raw_data(rgba, Format::Rgba8Srgb);
 
-    let loader = world.read_resource::<Loader>();
-    let texture_storage = world.read_resource::<AssetStorage<Texture>>();
-    loader.load_from_data(TextureData(builder), progress, &texture_storage)
+    loader.load_from_data(TextureData(builder), (), texture_storage)
+}
+
+/// Moves the captcha the open card is waiting for from the worker thread into the pool, image
+/// and audio, and drops any a closed card asked for, uploaded or not.
+pub struct CaptchaPoolSystem;
+
+impl<'s> System<'s> for CaptchaPoolSystem {
+    type SystemData = (
+        WriteExpect<'s, CaptchaPool>,
+        ReadExpect<'s, Loader>,
+        Read<'s, AssetStorage<Texture>>,
+        Read<'s, AssetStorage<Source>>,
+    );
+
+    fn run(&mut self, (mut pool, loader, texture_storage, source_storage): Self::SystemData) {
+        let wanted = pool.wanted.take();
+        if pool.requested != wanted {
+            pool.requested = None;
+        }
+        if pool.ready.as_ref().map(|captcha| &captcha.answer) != wanted.as_ref() {
+            pool.ready = None;
+        }
+        // Captchas nobody is waiting for any more are dropped before they become textures.
+        let image = pool
+            .incoming
+            .lock()
+            .expect("Unreachable: only this system takes the lock")
+            .try_iter()
+            .find(|image| Some(&image.answer) == wanted.as_ref());
+        if let Some(image) = image {
+            let texture = load_2d_texture(
+                &loader,
+                &texture_storage,
+                image.rgba,
+                CAPTCHA_WIDTH,
+                CAPTCHA_HEIGHT,
+            );
+            let audio = image
+                .audio
+                .map(|wav| loader.load_from_data(AudioData(wav), (), &source_storage));
+            pool.ready = Some(CaptchaData {
+                answer: image.answer,
+                texture,
+                audio,
+            });
+        }
+    }
 }
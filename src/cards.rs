@@ -1,33 +1,72 @@
 mod bucket;
-
-pub use self::bucket::{BucketAlertState, BucketRenderingSystem, BucketState, BucketUpdateSystem};
+mod drill;
+mod pickaxe;
+mod robot;
+mod shop;
+mod shovel;
+
+pub use self::bucket::{BucketAlertState, BucketState};
+pub use self::drill::DrillState;
+pub use self::pickaxe::PickaxeAlertState;
+pub use self::robot::ChallengeKind;
+pub use self::shop::{Upgrade, UpgradeLevels};
+use crate::assets::SpriteStorage;
 use crate::prelude::*;
-use log::info;
+use crate::replay::{ReplayAction, ReplayFrameSystem, ReplayRecorder};
+use crate::simulation::Simulation;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ToolId {
+    Shovel,
+    Bucket,
+    Drill,
+    Robot,
+    Shop,
+    Pickaxe,
+}
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ShovelAlertState {
     Ready,     // Don't do anything funky.
     NoBuckets, // Animate somehow!
+    Blocked,   // The current block is rock, and needs the pickaxe.
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum DrillAlertState {
     Ready,
     Drilling(f32),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RobotAlertState {
     CaptchaNeeded,
     Fetching,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum AlertState {
-    Shovel(ShovelAlertState), // The shovel button is disabled sometimes
-    Bucket(BucketAlertState), // Bucket might animate
-    Drill(DrillAlertState),   // Drill is either ready or actively drilling
-    Robot(RobotAlertState),   // Who knows
+    Shovel(ShovelAlertState),      // The shovel button is disabled sometimes
+    Bucket(BucketAlertState),      // Bucket might animate
+    Drill(DrillAlertState),        // Drill is either ready or actively drilling
+    Robot(usize, RobotAlertState), // Which robot, and who knows
+    Shop,                          // Always open for business
+    Pickaxe(PickaxeAlertState),    // Only useful when there's rock
+}
+
+impl AlertState {
+    pub fn tool(&self) -> ToolId {
+        match self {
+            AlertState::Shovel(_) => ToolId::Shovel,
+            AlertState::Bucket(_) => ToolId::Bucket,
+            AlertState::Drill(_) => ToolId::Drill,
+            AlertState::Robot(..) => ToolId::Robot,
+            AlertState::Shop => ToolId::Shop,
+            AlertState::Pickaxe(_) => ToolId::Pickaxe,
+        }
+    }
 }
 
 #[derive(Component, Debug)]
@@ -40,35 +79,156 @@ pub struct Alertable {
     pub clicked: bool, // Whether it was clicked in the last frame.
 }
 
-#[derive(Debug)]
-pub struct ShovelState {
-    pub click_progress: f32, // How long has it been since we clicked? Used for animation, perhaps.
+/// What a card wants done after it handles an action or a frame.
+#[derive(Debug, Default)]
+pub struct CardOutcome {
+    pub close: bool,
+    pub sound: Option<Sound>,
+}
+
+impl CardOutcome {
+    pub fn sound(sound: Sound) -> Self {
+        CardOutcome {
+            close: false,
+            sound: Some(sound),
+        }
+    }
+
+    pub fn close(sound: Option<Sound>) -> Self {
+        CardOutcome { close: true, sound }
+    }
+}
+
+/// Everything a card may touch while it's on screen.
+pub struct CardUi<'a, 's> {
+    pub digging: &'a DiggingStatus,
+    pub entities: &'a Entities<'s>,
+    pub transforms: &'a mut WriteStorage<'s, UiTransform>,
+    pub texts: &'a mut WriteStorage<'s, UiText>,
+    pub images: &'a mut WriteStorage<'s, UiImage>,
+    pub captchas: &'a mut CaptchaPool,
+    pub sprites: Option<&'a SpriteStorage>, // Missing until the game's sprites are loaded.
+    pub sounds: &'a SoundPlayer<'s>,
+}
+
+impl<'a, 's> CardUi<'a, 's> {
+    /// Like `UiFinder::find`, which can't be used alongside the writable transforms.
+    pub fn find(&self, id: &str) -> Option<Entity> {
+        (&**self.entities, &*self.transforms)
+            .join()
+            .find(|(_, transform)| transform.id == id)
+            .map(|(entity, _)| entity)
+    }
+
+    pub fn name(&self, entity: Entity) -> String {
+        get_ui_name(entity, &*self.transforms)
+    }
+}
+
+#[derive(SystemData)]
+pub struct CardUiData<'s> {
+    entities: Entities<'s>,
+    transforms: WriteStorage<'s, UiTransform>,
+    texts: WriteStorage<'s, UiText>,
+    images: WriteStorage<'s, UiImage>,
+    captchas: WriteExpect<'s, CaptchaPool>,
+    sprites: Option<Read<'s, SpriteStorage>>,
+    sounds: SoundPlayer<'s>,
+}
+
+impl<'s> CardUiData<'s> {
+    pub fn ui<'a>(&'a mut self, digging: &'a DiggingStatus) -> CardUi<'a, 's> {
+        CardUi {
+            digging,
+            entities: &self.entities,
+            transforms: &mut self.transforms,
+            texts: &mut self.texts,
+            images: &mut self.images,
+            captchas: &mut self.captchas,
+            sprites: self.sprites.as_deref(),
+            sounds: &self.sounds,
+        }
+    }
 }
 
-#[derive(Debug)]
-pub enum DrillState {
-    Idling(f32, f32, f32),
-    Running {
-        velocity: (f32, f32, f32),
-        position: (f32, f32, f32),
-    },
+/// A tool's card. Each tool implements this in its own module and registers it in `CardRegistry`.
+pub trait Card: std::fmt::Debug + Send + Sync {
+    /// The prefab to spawn instead of the one the tool's registered with, for cards that show
+    /// different things from one opening to the next.
+    fn prefab(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Turns a UI event aimed at this card into the action it stands for, if any.
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<ReplayAction>;
+
+    /// Carries out an action, whether the player just took it or a replay is playing it back.
+    fn act(
+        &mut self,
+        action: &ReplayAction,
+        simulation: &mut Simulation<'_>,
+        rng: &mut GameRng,
+    ) -> CardOutcome;
+
+    /// Runs every frame the card is open.
+    fn update(&mut self, _simulation: &mut Simulation<'_>, _dt: f32) -> CardOutcome {
+        CardOutcome::default()
+    }
+
+    /// Brings the card's UI elements up to date with its state.
+    fn render(&mut self, _ui: &mut CardUi<'_, '_>) {}
 }
 
-#[derive(Debug)]
-pub struct RobotState(String, String);
+pub type CardOpener = fn(AlertState, &DiggingStatus, &mut GameRng) -> Option<Box<dyn Card>>;
 
+struct CardKind {
+    prefab: &'static str,
+    open: CardOpener,
+}
+
+/// Which card each tool opens, and from which alertable states.
+pub struct CardRegistry {
+    kinds: HashMap<ToolId, CardKind>,
+}
+
+impl Default for CardRegistry {
+    fn default() -> Self {
+        let mut registry = CardRegistry {
+            kinds: HashMap::new(),
+        };
+        registry.register(ToolId::Shovel, "prefabs/shovel_card.ron", shovel::open);
+        registry.register(ToolId::Bucket, "prefabs/bucket_card.ron", bucket::open);
+        registry.register(ToolId::Drill, "prefabs/drill_card.ron", drill::open);
+        registry.register(ToolId::Robot, "prefabs/robot_card.ron", robot::open);
+        registry.register(ToolId::Shop, "prefabs/shop_card.ron", shop::open);
+        registry.register(ToolId::Pickaxe, "prefabs/pickaxe_card.ron", pickaxe::open);
+        registry
+    }
+}
+
+impl CardRegistry {
+    pub fn register(&mut self, tool: ToolId, prefab: &'static str, open: CardOpener) {
+        self.kinds.insert(tool, CardKind { prefab, open });
+    }
+
+    /// The prefab and fresh card for an alertable in this state, if it opens one right now.
+    pub fn open(
+        &self,
+        state: AlertState,
+        digging: &DiggingStatus,
+        rng: &mut GameRng,
+    ) -> Option<(&'static str, Box<dyn Card>)> {
+        let kind = self.kinds.get(&state.tool())?;
+        (kind.open)(state, digging, rng).map(|card| (card.prefab().unwrap_or(kind.prefab), card))
+    }
+}
+
+// An entity with this component will also have multiple UI elements to display itself.
+// CardSpawningSystem will respond to clicks on Alertables and spawn a DiggingCard from a prefab.
+// CardInputSystem, CardUpdateSystem and CardRenderingSystem hand input, time and rendering to it.
 #[derive(Component, Debug)]
 #[storage(VecStorage)]
-pub enum DiggingCard {
-    // An entity with this component will also have multiple UI elements to display itself.
-    // CardSpawningSystem will respond to clicks on Alertables and spawn a DiggingCard from a prefab.
-    // CardUpdateSystem will update the internal state of these cards based on user input or whatever.
-    // CardRenderingSystem will update the UI elements based on the internal state of these cards.
-    Shovel(ShovelState),
-    Bucket(BucketState),
-    Drill(DrillState),
-    Robot(RobotState),
-}
+pub struct DiggingCard(pub Box<dyn Card>);
 
 pub struct AlertableUpdateSystem {
     reader_id: ReaderId<UiEvent>,
@@ -100,26 +260,32 @@ impl<'s> System<'s> for AlertableUpdateSystem {
                 }
                 _ => {}
             }
-            match (digging.robot_status, alertable.state) {
-                (
-                    RobotStatus::Running { .. },
-                    AlertState::Robot(RobotAlertState::CaptchaNeeded),
-                ) => {
-                    alertable.state = AlertState::Robot(RobotAlertState::Fetching);
-                }
-                (RobotStatus::Idling, AlertState::Robot(RobotAlertState::Fetching)) => {
-                    alertable.state = AlertState::Robot(RobotAlertState::CaptchaNeeded);
+            if let AlertState::Robot(robot, robot_state) = alertable.state {
+                match (digging.robots.get(robot), robot_state) {
+                    (Some(RobotStatus::Running { .. }), RobotAlertState::CaptchaNeeded) => {
+                        alertable.state = AlertState::Robot(robot, RobotAlertState::Fetching);
+                    }
+                    (Some(RobotStatus::Idling), RobotAlertState::Fetching) => {
+                        alertable.state = AlertState::Robot(robot, RobotAlertState::CaptchaNeeded);
+                    }
+                    _ => {}
                 }
-                _ => {}
             }
-            match (digging.can_scoop(), alertable.state) {
-                (false, AlertState::Shovel(ShovelAlertState::Ready)) => {
-                    alertable.state = AlertState::Shovel(ShovelAlertState::NoBuckets);
-                }
-                (true, AlertState::Shovel(ShovelAlertState::NoBuckets)) => {
-                    alertable.state = AlertState::Shovel(ShovelAlertState::Ready);
-                }
-                _ => {}
+            if let AlertState::Shovel(_) = alertable.state {
+                alertable.state = AlertState::Shovel(if digging.blocked_by_rock() {
+                    ShovelAlertState::Blocked
+                } else if !digging.can_scoop() {
+                    ShovelAlertState::NoBuckets
+                } else {
+                    ShovelAlertState::Ready
+                });
+            }
+            if let AlertState::Pickaxe(_) = alertable.state {
+                alertable.state = AlertState::Pickaxe(if digging.blocked_by_rock() {
+                    PickaxeAlertState::Rock
+                } else {
+                    PickaxeAlertState::Waiting
+                });
             }
             match (digging.no_buckets(), alertable.state) {
                 (false, AlertState::Bucket(BucketAlertState::Empty)) => {
@@ -165,40 +331,30 @@ impl<'s> System<'s> for CardSpawningSystem {
         WriteStorage<'s, DiggingCard>,
         WidgetSpawner<'s>,
         Entities<'s>,
+        Read<'s, DiggingStatus>,
+        Read<'s, CardRegistry>,
+        Write<'s, ReplayRecorder>,
+        Write<'s, GameRng>,
     );
 
-    fn run(&mut self, (alertables, mut cards, mut spawner, entities): Self::SystemData) {
+    fn run(
+        &mut self,
+        (alertables, mut cards, mut spawner, entities, digging, registry, mut recorder, mut rng): Self::SystemData,
+    ) {
         /*
          Loop through alertables, check if any have been clicked based on the state. If so, spawn a card. Also, maybe, delete any old cards.
          It's maybe a good idea to split this up from AlertableSystem, just to keep systems dealing only with single responsibilities.
         */
         for alertable in alertables.join() {
             if alertable.clicked {
-                if let Some((prefab, card)) = match alertable.state {
-                    AlertState::Shovel(ShovelAlertState::Ready) => Some((
-                        "prefabs/shovel_card.ron",
-                        DiggingCard::Shovel(ShovelState { click_progress: 0. }),
-                    )),
-                    AlertState::Bucket(BucketAlertState::Filled(_)) => Some((
-                        "prefabs/bucket_card.ron",
-                        DiggingCard::Bucket(BucketState::Empty),
-                    )),
-                    AlertState::Drill(DrillAlertState::Ready) => Some((
-                        "prefabs/drill_card.ron",
-                        DiggingCard::Drill(DrillState::Idling(0., 0., 0.)),
-                    )),
-                    AlertState::Robot(RobotAlertState::CaptchaNeeded) => Some((
-                        "prefabs/robot_card.ron",
-                        DiggingCard::Robot(RobotState("".to_string(), "".to_string())),
-                    )),
-                    _ => None,
-                } {
+                if let Some((prefab, card)) = registry.open(alertable.state, &digging, &mut rng) {
+                    recorder.record(digging.time_played, ReplayAction::OpenCard(alertable.state));
                     for (_card, entity) in (&cards, &entities).join() {
                         entities.delete(entity).expect("Double delete");
                     }
                     let entity = spawner.spawn_ui_widget(prefab, CARD_POSITION);
                     cards
-                        .insert(entity, card)
+                        .insert(entity, DiggingCard(card))
                         .expect("Unreachable, entity just created");
                 }
             }
@@ -220,6 +376,17 @@ fn get_card_entity<'s>(
     }
 }
 
+fn finish(outcome: CardOutcome, entity: Entity, entities: &Entities, sounds: &SoundPlayer) {
+    if let Some(sound) = outcome.sound {
+        sounds.play(sound);
+    }
+    if outcome.close {
+        entities
+            .delete(entity)
+            .expect("Unreachable, entitity definitely exists");
+    }
+}
+
 pub struct CardInputSystem {
     reader_id: ReaderId<UiEvent>,
 }
@@ -229,142 +396,34 @@ impl<'s> System<'s> for CardInputSystem {
         Read<'s, EventChannel<UiEvent>>,
         Write<'s, DiggingStatus>,
         WriteStorage<'s, DiggingCard>,
-        Read<'s, InputHandler<StringBindings>>,
         ReadStorage<'s, Parent>,
-        ReadStorage<'s, UiTransform>,
-        WriteStorage<'s, UiText>,
-        Entities<'s>,
-        Read<'s, Time>,
-        SoundPlayer<'s>,
+        Write<'s, GameRng>,
+        Write<'s, ReplayRecorder>,
+        CardUiData<'s>,
     );
 
     fn run(
         &mut self,
-        (
-            events,
-            mut digging,
-            mut cards,
-            input,
-            parents,
-            transforms,
-            mut texts,
-            entities,
-            time,
-            sounds,
-        ): Self::SystemData,
+        (events, mut digging, mut cards, parents, mut rng, mut recorder, mut ui_data): Self::SystemData,
     ) {
-        /*
-         Loop through cards, check the mouse state, and update the card.
-         We may need some sort of abstraction here (DiggingCard implements its own update function?) or just a simple match block.
-        */
         for event in events.read(&mut self.reader_id) {
-            if let Some((ent, mut card)) = get_card_entity(event.target, &cards, &parents)
+            if let Some((ent, DiggingCard(card))) = get_card_entity(event.target, &cards, &parents)
                 .and_then(|ent| cards.get_mut(ent).map(|card| (ent, card)))
             {
-                match card {
-                    DiggingCard::Robot(RobotState(captcha_value, current_value)) => {
-                        if event.event_type == UiEventType::ValueChange
-                            && get_ui_name(event.target, &transforms).eq("captcha_input")
-                        {
-                            sounds.robot_captcha_key();
-                            *card = DiggingCard::Robot(RobotState(
-                                captcha_value.clone(),
-                                texts
-                                    .get(event.target)
-                                    .map(|ui| ui.text.clone())
-                                    .unwrap_or("".to_string()),
-                            ));
-                        } else if (event.event_type == UiEventType::ValueCommit
-                            && get_ui_name(event.target, &transforms).eq("captcha_input"))
-                            || (event.event_type == UiEventType::Click
-                                && get_ui_name(event.target, &transforms).eq("solve_captcha"))
-                        {
-                            if (*current_value).eq(captcha_value) {
-                                sounds.robot_captcha_success();
-                                digging.solve_captcha();
-                                entities
-                                    .delete(ent)
-                                    .expect("Unreachable, entitity definitely exists");
-                            } else {
-                                for (transform, text) in (&transforms, &mut texts).join() {
-                                    if transform.id.eq("captcha_input") {
-                                        sounds.robot_captcha_fail();
-                                        text.text = "".to_string();
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    DiggingCard::Shovel(_) => {
-                        if event.event_type != UiEventType::Click {
-                            continue;
-                        }
-                        if get_ui_name(event.target, &transforms).eq("shovel_dirt") {
-                            sounds.shovel();
-                            digging.scoop(true);
-                            if !digging.can_scoop() {
-                                entities
-                                    .delete(ent)
-                                    .expect("Unreachable, entitity definitely exists");
-                            }
-                        }
-                    }
-                    DiggingCard::Drill(drill_state) => {
-                        if event.event_type != UiEventType::Click {
-                            continue;
-                        }
-                        if get_ui_name(event.target, &transforms).eq("pull_drill") {
-                            match drill_state {
-                                DrillState::Idling(a, b, c)
-                                | DrillState::Running {
-                                    position: (a, b, c),
-                                    ..
-                                } => {
-                                    digging.drill_pulls += 1;
-                                    *card = DiggingCard::Drill(DrillState::Running {
-                                        position: (*a, *b, *c),
-                                        velocity: (
-                                            random::<f32>() * 10.,
-                                            random::<f32>() * 10.,
-                                            random::<f32>() * 10.,
-                                        ),
-                                    });
-                                    sounds.drill_spin();
-                                }
-                            }
-                        }
-                    }
-                    DiggingCard::Bucket(bucket) => {
-                        let is_targeted = get_ui_name(event.target, &transforms).eq("fill_bucket");
-                        match bucket {
-                            BucketState::Empty | BucketState::Unheld(_) => {
-                                if event.event_type == UiEventType::ClickStart && is_targeted {
-                                    info!("held bucket");
-                                    *card = DiggingCard::Bucket(BucketState::Held(0.));
-                                }
-                            }
-                            BucketState::Held(progress) => {
-                                if event.event_type == UiEventType::HoverStop
-                                    || event.event_type == UiEventType::ClickStop
-                                    || !is_targeted
-                                {
-                                    info!("let go of bucket");
-                                    *card = DiggingCard::Bucket(BucketState::Unheld(*progress));
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                    _ => {}
+                let action = card.handle_event(event, &mut ui_data.ui(&digging));
+                if let Some(action) = action {
+                    recorder.record(digging.time_played, action.clone());
+                    let outcome = card.act(&action, &mut Simulation::new(&mut digging), &mut rng);
+                    finish(outcome, ent, &ui_data.entities, &ui_data.sounds);
                 }
             }
         }
     }
 }
 
-pub struct DrillUpdateSystem;
+pub struct CardUpdateSystem;
 
-impl<'s> System<'s> for DrillUpdateSystem {
+impl<'s> System<'s> for CardUpdateSystem {
     type SystemData = (
         Write<'s, DiggingStatus>,
         WriteStorage<'s, DiggingCard>,
@@ -372,188 +431,30 @@ impl<'s> System<'s> for DrillUpdateSystem {
         Read<'s, Time>,
         SoundPlayer<'s>,
     );
-    fn run(&mut self, (mut digging, mut cards, entities, time, sounds): Self::SystemData) {
-        for (card, entity) in (&mut cards, &entities).join() {
-            if let DiggingCard::Drill(DrillState::Running { position, velocity }) = card {
-                position.0 += velocity.0 * time.delta_seconds();
-                position.1 += velocity.1 * time.delta_seconds();
-                position.2 += velocity.2 * time.delta_seconds();
-                velocity.0 -= 10. * time.delta_seconds();
-                velocity.1 -= 10. * time.delta_seconds();
-                velocity.2 -= 10. * time.delta_seconds();
-                if position.0 > 1. {
-                    position.0 -= 1.;
-                }
-                if position.1 > 1. {
-                    position.1 -= 1.;
-                }
-                if position.2 > 1. {
-                    position.2 -= 1.;
-                }
-                if velocity.0 < 0. {
-                    velocity.0 = 0.
-                }
-                if velocity.1 < 0. {
-                    velocity.1 = 0.
-                }
-                if velocity.2 < 0. {
-                    velocity.2 = 0.
-                }
-                if velocity.0 == 0. && velocity.1 == 0. && velocity.2 == 0. {
-                    // Maybe do some sound effects here.
-                    if position.0 > 0.25
-                        && position.0 < 0.75
-                        && position.1 > 0.25
-                        && position.1 < 0.75
-                        && position.2 > 0.25
-                        && position.2 < 0.75
-                    {
-                        sounds.drill_start();
-                        digging.drill();
-                        entities
-                            .delete(entity)
-                            .expect("Unreachable, entitity definitely exists");
-                    }
-                }
-            }
-        }
-    }
-}
-
-pub struct ShovelRenderingSystem;
-
-impl<'s> System<'s> for ShovelRenderingSystem {
-    // I'm not 100% sure the component to use for the UI elements here. Probably UIContainer?
-    type SystemData = (
-        Read<'s, DiggingStatus>,
-        ReadStorage<'s, DiggingCard>,
-        UiFinder<'s>,
-        WriteStorage<'s, UiImage>,
-    );
-
-    fn run(&mut self, (digging, cards, finder, mut images): Self::SystemData) {
-        /*
-         Loop through cards (really, only the one on screen, probably), update the UI based on card state.
-        */
-        for card in cards.join() {
-            match card {
-                DiggingCard::Shovel(_) => {
-                    if let Some(mut image) = finder
-                        .find("shovel_bucket")
-                        .and_then(|ent| images.get_mut(ent))
-                    {
-                        if digging.scoops_in_top_bucket() == 0 {
-                            update_texture(image, Some(0.25), Some(0.375), Some(0.125), Some(0.25))
-                        } else {
-                            update_texture(
-                                image,
-                                Some(0.125 * digging.scoops_in_top_bucket() as f32 - 0.125),
-                                Some(0.125 * digging.scoops_in_top_bucket() as f32),
-                                Some(0.25),
-                                Some(0.375),
-                            )
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
-    }
-}
-
-pub struct DrillRenderingSystem;
-
-impl<'s> System<'s> for DrillRenderingSystem {
-    // I'm not 100% sure the component to use for the UI elements here. Probably UIContainer?
-    type SystemData = (
-        Read<'s, DiggingStatus>,
-        ReadStorage<'s, DiggingCard>,
-        WriteStorage<'s, UiTransform>,
-        WriteStorage<'s, UiImage>,
-    );
 
-    fn run(&mut self, (digging, cards, mut transforms, mut images): Self::SystemData) {
-        /*
-         Loop through cards (really, only the one on screen, probably), update the UI based on card state.
-        */
-        for card in cards.join() {
-            match card {
-                DiggingCard::Drill(DrillState::Idling(a, b, c))
-                | DiggingCard::Drill(DrillState::Running {
-                    position: (a, b, c),
-                    ..
-                }) => {
-                    for (mut transform, mut image) in (&mut transforms, &mut images).join() {
-                        match transform.id.as_ref() {
-                            "drill_slot_0" => {
-                                transform.local_y = a * 32. - 16.;
-                            }
-                            "drill_slot_1" => {
-                                transform.local_y = b * 32. - 16.;
-                            }
-                            "drill_slot_2" => {
-                                transform.local_y = c * 32. - 16.;
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                _ => {}
-            }
+    fn run(&mut self, (mut digging, mut cards, entities, time, sounds): Self::SystemData) {
+        for (DiggingCard(card), entity) in (&mut cards, &entities).join() {
+            let outcome = card.update(&mut Simulation::new(&mut digging), time.delta_seconds());
+            finish(outcome, entity, &entities, &sounds);
         }
     }
 }
 
-pub struct RobotRenderingSystem;
+pub struct CardRenderingSystem;
 
-impl<'s> System<'s> for RobotRenderingSystem {
-    // I'm not 100% sure the component to use for the UI elements here. Probably UIContainer?
+impl<'s> System<'s> for CardRenderingSystem {
     type SystemData = (
         Read<'s, DiggingStatus>,
-        Read<'s, Vec<CaptchaData>>,
         WriteStorage<'s, DiggingCard>,
-        WriteStorage<'s, UiTransform>,
-        WriteStorage<'s, UiImage>,
-        Read<'s, AssetStorage<Texture>>,
-        Entities<'s>,
+        CardUiData<'s>,
     );
 
-    fn run(
-        &mut self,
-        (digging, captchas, mut cards, mut transforms, mut images, assets, entities): Self::SystemData,
-    ) {
+    fn run(&mut self, (digging, mut cards, mut ui_data): Self::SystemData) {
         /*
          Loop through cards (really, only the one on screen, probably), update the UI based on card state.
         */
-        for mut card in (&mut cards).join() {
-            match card {
-                DiggingCard::Robot(RobotState(captcha_val, _typed_val)) => {
-                    println!("{:?} {:?}", captcha_val, _typed_val);
-                    let cloned = captcha_val.clone();
-                    for (mut transform, entity) in (&mut transforms, &entities).join() {
-                        if transform.id.eq("captcha") {
-                            match (cloned.as_ref(), images.get(entity)) {
-                                ("", _) | (_, Some(UiImage::SolidColor(_))) => {
-                                    let range =
-                                        rand::distributions::Uniform::new(0, captchas.len());
-                                    let captcha = captchas
-                                        .get(thread_rng().sample(range))
-                                        .expect("Bad randomness");
-                                    *card = DiggingCard::Robot(RobotState(
-                                        captcha.answer.to_string(),
-                                        "".to_string(),
-                                    ));
-                                    images
-                                        .insert(entity, UiImage::Texture(captcha.texture.clone()))
-                                        .expect("Unreachable, entity exists");
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                }
-                _ => {}
-            }
+        for DiggingCard(card) in (&mut cards).join() {
+            card.render(&mut ui_data.ui(&digging));
         }
     }
 }
@@ -566,6 +467,8 @@ impl SystemBundle<'_, '_> for CardsBundle {
         world: &mut World,
         dispatcher: &mut DispatcherBuilder<'_, '_>,
     ) -> Result<(), Error> {
+        world.insert(CardRegistry::default());
+        dispatcher.add(ReplayFrameSystem, "replay_frame", &[]);
         let mut ui_events = <Write<EventChannel<UiEvent>>>::fetch(world);
         let alert_reader = ui_events.register_reader();
         dispatcher.add(
@@ -576,25 +479,17 @@ impl SystemBundle<'_, '_> for CardsBundle {
             &[],
         );
         dispatcher.add(AlertableRenderSystem, "alert_render", &["alert_update"]);
-        dispatcher.add(CardSpawningSystem, "card_spawn", &[]);
+        dispatcher.add(CardSpawningSystem, "card_spawn", &["replay_frame"]);
         let card_reader = ui_events.register_reader();
         dispatcher.add(
             CardInputSystem {
                 reader_id: card_reader,
             },
             "card_input",
-            &[],
-        );
-        dispatcher.add(DrillUpdateSystem, "drill_update", &["card_input"]);
-        dispatcher.add(BucketUpdateSystem, "bucket_update", &["card_input"]);
-        dispatcher.add(
-            BucketRenderingSystem,
-            "bucket_card_render",
-            &["bucket_update"],
+            &["replay_frame"],
         );
-        dispatcher.add(ShovelRenderingSystem, "shovel_render", &["card_input"]);
-        dispatcher.add(DrillRenderingSystem, "drill_render", &["drill_update"]);
-        dispatcher.add(RobotRenderingSystem, "robot_card_render", &["card_input"]);
+        dispatcher.add(CardUpdateSystem, "card_update", &["card_input"]);
+        dispatcher.add(CardRenderingSystem, "card_render", &["card_update"]);
         Ok(())
     }
 }
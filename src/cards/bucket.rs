@@ -1,9 +1,11 @@
-use super::DiggingCard;
+use super::{AlertState, Card, CardOutcome, CardUi};
 use crate::prelude::*;
+use crate::replay::ReplayAction;
+use crate::simulation::{Simulation, SimulationEvent};
+use log::info;
+use serde::{Deserialize, Serialize};
 
-pub const BUCKET_SUCCESS_TIME: f32 = 1.;
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum BucketAlertState {
     Empty,       // Don't do anything funky.
     Filled(f32), // Animate somehow!
@@ -17,93 +19,78 @@ pub enum BucketState {
     Finished(f32), // The player has gotten to the top, and the bucket is now emptying. Used for animation, perhaps.
 }
 
-pub struct BucketUpdateSystem;
+pub fn open(
+    state: AlertState,
+    _digging: &DiggingStatus,
+    _rng: &mut GameRng,
+) -> Option<Box<dyn Card>> {
+    match state {
+        AlertState::Bucket(BucketAlertState::Filled(_)) => Some(Box::new(BucketState::Empty)),
+        _ => None,
+    }
+}
 
-impl<'s> System<'s> for BucketUpdateSystem {
-    type SystemData = (
-        Write<'s, DiggingStatus>,
-        WriteStorage<'s, DiggingCard>,
-        Entities<'s>,
-        Read<'s, Time>,
-        SoundPlayer<'s>,
-    );
-    fn run(&mut self, (mut digging, mut cards, entities, time, sounds): Self::SystemData) {
-        for (card, entity) in (&mut cards, &entities).join() {
-            if let DiggingCard::Bucket(state) = card {
-                match state {
-                    BucketState::Held(progress) => {
-                        *progress = *progress + time.delta_seconds();
-                        if *progress > BUCKET_SUCCESS_TIME {
-                            digging.empty_bucket();
-                            sounds.empty_bucket();
-                            if digging.no_buckets() {
-                                *card = DiggingCard::Bucket(BucketState::Finished(1.));
-                            } else {
-                                *card = DiggingCard::Bucket(BucketState::Unheld(*progress));
-                            }
-                        }
-                    }
-                    BucketState::Finished(_) => {
-                        entities
-                            .delete(entity)
-                            .expect("Unreachable, entitity definitely exists");
-                    }
-                    _ => {}
+impl Card for BucketState {
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<ReplayAction> {
+        let is_targeted = ui.name(event.target).eq("fill_bucket");
+        match self {
+            BucketState::Empty | BucketState::Unheld(_) => {
+                if event.event_type == UiEventType::ClickStart && is_targeted {
+                    info!("held bucket");
+                    return Some(ReplayAction::BucketHoldStart);
                 }
             }
+            BucketState::Held(_) => {
+                if event.event_type == UiEventType::HoverStop
+                    || event.event_type == UiEventType::ClickStop
+                    || !is_targeted
+                {
+                    info!("let go of bucket");
+                    return Some(ReplayAction::BucketHoldStop);
+                }
+            }
+            _ => {}
         }
+        None
     }
-}
 
-pub struct BucketRenderingSystem;
+    fn act(
+        &mut self,
+        action: &ReplayAction,
+        simulation: &mut Simulation<'_>,
+        _rng: &mut GameRng,
+    ) -> CardOutcome {
+        match action {
+            ReplayAction::BucketHoldStart => simulation.grab_bucket(self),
+            ReplayAction::BucketHoldStop => simulation.release_bucket(self),
+            _ => {}
+        }
+        CardOutcome::default()
+    }
 
-impl<'s> System<'s> for BucketRenderingSystem {
-    // I'm not 100% sure the component to use for the UI elements here. Probably UIContainer?
-    type SystemData = (
-        Read<'s, DiggingStatus>,
-        ReadStorage<'s, DiggingCard>,
-        WriteStorage<'s, UiTransform>,
-        WriteStorage<'s, UiImage>,
-    );
+    fn update(&mut self, simulation: &mut Simulation<'_>, dt: f32) -> CardOutcome {
+        if let BucketState::Finished(_) = self {
+            CardOutcome::close(None)
+        } else if let Some(SimulationEvent::BucketEmptied) = simulation.hold_bucket(self, dt) {
+            CardOutcome::sound(Sound::EmptyBucket)
+        } else {
+            CardOutcome::default()
+        }
+    }
 
-    fn run(&mut self, (digging, cards, mut transforms, mut images): Self::SystemData) {
-        /*
-         Loop through cards (really, only the one on screen, probably), update the UI based on card state.
-        */
-        for card in cards.join() {
-            match card {
-                DiggingCard::Bucket(BucketState::Held(progress)) => {
-                    for (mut transform, mut image) in (&mut transforms, &mut images).join() {
-                        if transform.id.eq("dump_bucket_bar") {
-                            let width = (1. - progress) * 117.;
-                            let x = 65. + (progress * 117. * 0.5);
-                            transform.local_x = x;
-                            transform.width = width;
-                        }
-                    }
-                }
-                DiggingCard::Bucket(BucketState::Finished(_)) => {
-                    for (mut transform, mut image) in (&mut transforms, &mut images).join() {
-                        if transform.id.eq("dump_bucket_bar") {
-                            let width = 0.;
-                            let x = 65.;
-                            transform.local_x = x;
-                            transform.width = width;
-                        }
-                    }
-                }
-                DiggingCard::Bucket(BucketState::Unheld(_))
-                | DiggingCard::Bucket(BucketState::Empty) => {
-                    for (mut transform, mut image) in (&mut transforms, &mut images).join() {
-                        if transform.id.eq("dump_bucket_bar") {
-                            let width = 117.;
-                            let x = 65.;
-                            transform.local_x = x;
-                            transform.width = width;
-                        }
-                    }
-                }
-                _ => {}
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        let (x, width) = match self {
+            BucketState::Held(progress) => {
+                let progress = *progress / ui.digging.balance.bucket_success_time;
+                (65. + (progress * 117. * 0.5), (1. - progress) * 117.)
+            }
+            BucketState::Finished(_) => (65., 0.),
+            BucketState::Unheld(_) | BucketState::Empty => (65., 117.),
+        };
+        for transform in (&mut *ui.transforms).join() {
+            if transform.id.eq("dump_bucket_bar") {
+                transform.local_x = x;
+                transform.width = width;
             }
         }
     }
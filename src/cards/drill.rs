@@ -0,0 +1,81 @@
+use super::{AlertState, Card, CardOutcome, CardUi, DrillAlertState};
+use crate::prelude::*;
+use crate::replay::ReplayAction;
+use crate::simulation::Simulation;
+
+#[derive(Debug)]
+pub enum DrillState {
+    Idling(f32, f32, f32),
+    Running {
+        velocity: (f32, f32, f32),
+        position: (f32, f32, f32),
+    },
+}
+
+pub fn open(
+    state: AlertState,
+    _digging: &DiggingStatus,
+    _rng: &mut GameRng,
+) -> Option<Box<dyn Card>> {
+    match state {
+        AlertState::Drill(DrillAlertState::Ready) => Some(Box::new(DrillState::Idling(0., 0., 0.))),
+        _ => None,
+    }
+}
+
+impl Card for DrillState {
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<ReplayAction> {
+        if event.event_type == UiEventType::Click && ui.name(event.target).eq("pull_drill") {
+            Some(ReplayAction::PullDrill)
+        } else {
+            None
+        }
+    }
+
+    fn act(
+        &mut self,
+        action: &ReplayAction,
+        simulation: &mut Simulation<'_>,
+        rng: &mut GameRng,
+    ) -> CardOutcome {
+        match action {
+            ReplayAction::PullDrill => {
+                simulation.pull_cord(self, rng);
+                CardOutcome::sound(Sound::DrillSpin)
+            }
+            _ => CardOutcome::default(),
+        }
+    }
+
+    fn update(&mut self, simulation: &mut Simulation<'_>, dt: f32) -> CardOutcome {
+        if simulation.spin_reels(self, dt) {
+            CardOutcome::close(Some(Sound::DrillStart))
+        } else {
+            CardOutcome::default()
+        }
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        let (a, b, c) = match self {
+            DrillState::Idling(a, b, c)
+            | DrillState::Running {
+                position: (a, b, c),
+                ..
+            } => (*a, *b, *c),
+        };
+        for transform in (&mut *ui.transforms).join() {
+            match transform.id.as_ref() {
+                "drill_slot_0" => {
+                    transform.local_y = a * 32. - 16.;
+                }
+                "drill_slot_1" => {
+                    transform.local_y = b * 32. - 16.;
+                }
+                "drill_slot_2" => {
+                    transform.local_y = c * 32. - 16.;
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use super::{AlertState, Card, CardOutcome, CardUi};
+use crate::prelude::*;
+use crate::replay::ReplayAction;
+use crate::simulation::Simulation;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PickaxeAlertState {
+    Waiting, // Nothing to break.
+    Rock,    // The shovel is stuck on rock.
+}
+
+#[derive(Debug)]
+pub struct PickaxeState;
+
+pub fn open(
+    state: AlertState,
+    _digging: &DiggingStatus,
+    _rng: &mut GameRng,
+) -> Option<Box<dyn Card>> {
+    match state {
+        AlertState::Pickaxe(PickaxeAlertState::Rock) => Some(Box::new(PickaxeState)),
+        _ => None,
+    }
+}
+
+impl Card for PickaxeState {
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<ReplayAction> {
+        if event.event_type == UiEventType::Click && ui.name(event.target).eq("swing_pickaxe") {
+            Some(ReplayAction::SwingPickaxe)
+        } else {
+            None
+        }
+    }
+
+    fn act(
+        &mut self,
+        action: &ReplayAction,
+        simulation: &mut Simulation<'_>,
+        _rng: &mut GameRng,
+    ) -> CardOutcome {
+        match action {
+            ReplayAction::SwingPickaxe => match simulation.swing_pickaxe() {
+                Some(true) => CardOutcome::close(Some(Sound::PickaxeBreak)),
+                Some(false) => CardOutcome::sound(Sound::PickaxeHit),
+                None => CardOutcome::close(None),
+            },
+            _ => CardOutcome::default(),
+        }
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        let digging = ui.digging;
+        if let Some(text) = ui.find("rock_hits").and_then(|ent| ui.texts.get_mut(ent)) {
+            text.text = format!(
+                "Cracks: {}/{}",
+                digging.rock_hits, digging.balance.pickaxe_hits
+            );
+        }
+    }
+}
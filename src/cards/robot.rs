@@ -0,0 +1,202 @@
+mod arithmetic;
+mod memory;
+mod slider;
+mod text;
+mod tiles;
+
+use super::{AlertState, Card, CardOutcome, CardUi, RobotAlertState};
+use crate::balance::{CaptchaValidation, CaptchaVerdict};
+use crate::prelude::*;
+use crate::replay::ReplayAction;
+use crate::simulation::Simulation;
+use serde::{Deserialize, Serialize};
+
+/// The kinds of puzzle a robot can ask for. Each captcha tier lists the ones robots pick from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChallengeKind {
+    Captcha,    // Type the distorted text.
+    Arithmetic, // Work out a sum.
+    Memory,     // Repeat a sequence of flashing lights.
+    DirtTiles,  // Pick out the dirt among the other tiles.
+    Slider,     // Slide the numbered tiles back in order.
+}
+
+/// A puzzle a robot wants solved before it sets off.
+///
+/// Whatever the puzzle, the player's answer and the right one end up as text in the
+/// `CaptchaCommit` action, so replays check it against exactly what the player saw.
+pub trait RobotChallenge: std::fmt::Debug + Send + Sync {
+    fn prefab(&self) -> &'static str;
+
+    /// Turns a UI event into the player's answer, once they commit to one.
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<String>;
+
+    /// The right answer, or empty while the puzzle isn't on screen yet.
+    fn answer(&self) -> &str;
+
+    /// Which captcha tier the puzzle was made for.
+    fn tier(&self) -> usize;
+
+    /// How answers are checked, given the balance's policy for captchas. Only captcha glyphs
+    /// get misread, so other puzzles take their exact answer.
+    fn validation(&self, _captchas: &CaptchaValidation) -> CaptchaValidation {
+        CaptchaValidation::exact()
+    }
+
+    /// Sets the puzzle up for another go after a wrong answer.
+    fn retry(&mut self) {}
+
+    fn update(&mut self, _dt: f32) {}
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>);
+}
+
+#[derive(Debug)]
+pub struct RobotState {
+    robot: usize, // Which robot this challenge sends off.
+    challenge: Box<dyn RobotChallenge>,
+}
+
+pub fn open(
+    state: AlertState,
+    digging: &DiggingStatus,
+    rng: &mut GameRng,
+) -> Option<Box<dyn Card>> {
+    match state {
+        AlertState::Robot(robot, RobotAlertState::CaptchaNeeded) => Some(Box::new(RobotState {
+            robot,
+            challenge: challenge(digging, rng),
+        })),
+        _ => None,
+    }
+}
+
+/// Picks one of the kinds the dig's captcha tier allows, at random.
+fn challenge(digging: &DiggingStatus, rng: &mut GameRng) -> Box<dyn RobotChallenge> {
+    let index = digging.captcha_tier();
+    let tier = &digging.balance.captcha_tiers[index]; // Validated balances start with a tier at 0m.
+    let rng = rng.stream(RngStream::Captchas);
+    let kind = if tier.challenges.is_empty() {
+        ChallengeKind::Captcha
+    } else {
+        tier.challenges[rng.gen_range(0..tier.challenges.len())]
+    };
+    match kind {
+        ChallengeKind::Captcha => Box::new(text::TextCaptcha::new(index, tier, digging, rng)),
+        ChallengeKind::Arithmetic => Box::new(arithmetic::Arithmetic::new(index, rng)),
+        ChallengeKind::Memory => Box::new(memory::Memory::new(index, tier, rng)),
+        ChallengeKind::DirtTiles => Box::new(tiles::DirtTiles::new(index, digging, rng)),
+        ChallengeKind::Slider => Box::new(slider::Slider::new(index, rng)),
+    }
+}
+
+impl Card for RobotState {
+    fn prefab(&self) -> Option<&'static str> {
+        Some(self.challenge.prefab())
+    }
+
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<ReplayAction> {
+        let typed = self.challenge.handle_event(event, ui)?;
+        if self.challenge.answer().is_empty() {
+            return None;
+        }
+        Some(ReplayAction::CaptchaCommit {
+            robot: self.robot,
+            typed,
+            answer: self.challenge.answer().to_string(),
+            tier: self.challenge.tier(),
+        })
+    }
+
+    fn act(
+        &mut self,
+        action: &ReplayAction,
+        simulation: &mut Simulation<'_>,
+        _rng: &mut GameRng,
+    ) -> CardOutcome {
+        match action {
+            ReplayAction::CaptchaCommit {
+                robot,
+                typed,
+                answer,
+                tier,
+            } => {
+                let validation = self
+                    .challenge
+                    .validation(&simulation.balance().captcha_validation);
+                match simulation.solve_captcha(*robot, *tier, answer, typed, &validation) {
+                    CaptchaVerdict::Correct | CaptchaVerdict::Partial => {
+                        CardOutcome::close(Some(Sound::RobotCaptchaSuccess))
+                    }
+                    CaptchaVerdict::Wrong => {
+                        self.challenge.retry();
+                        CardOutcome::sound(Sound::RobotCaptchaFail)
+                    }
+                }
+            }
+            _ => CardOutcome::default(),
+        }
+    }
+
+    fn update(&mut self, _simulation: &mut Simulation<'_>, dt: f32) -> CardOutcome {
+        self.challenge.update(dt);
+        CardOutcome::default()
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        self.challenge.render(ui);
+    }
+}
+
+/// The text box and Solve button the typed challenges share, as `captcha_input` and
+/// `solve_captcha` in their prefabs.
+#[derive(Debug, Default)]
+struct TypedAnswer {
+    typed: String,
+    clear_input: bool, // A wrong answer empties the input on the next render.
+}
+
+impl TypedAnswer {
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<String> {
+        let name = ui.name(event.target);
+        if event.event_type == UiEventType::ValueChange && name.eq("captcha_input") {
+            ui.sounds.robot_captcha_key();
+            self.typed = ui
+                .texts
+                .get(event.target)
+                .map(|ui| ui.text.clone())
+                .unwrap_or("".to_string());
+            None
+        } else if (event.event_type == UiEventType::ValueCommit && name.eq("captcha_input"))
+            || (event.event_type == UiEventType::Click && name.eq("solve_captcha"))
+        {
+            Some(self.typed.clone())
+        } else {
+            None
+        }
+    }
+
+    fn clear(&mut self) {
+        self.clear_input = true;
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        if self.clear_input {
+            self.clear_input = false;
+            self.typed = "".to_string();
+            if let Some(text) = ui
+                .find("captcha_input")
+                .and_then(|ent| ui.texts.get_mut(ent))
+            {
+                text.text = "".to_string();
+            }
+        }
+    }
+}
+
+/// Sets the text of the element with this id, if the card has one.
+fn set_text(ui: &mut CardUi<'_, '_>, id: &str, text: String) {
+    if let Some(label) = ui.find(id).and_then(|ent| ui.texts.get_mut(ent)) {
+        label.text = text;
+    }
+}
@@ -0,0 +1,65 @@
+use super::{set_text, RobotChallenge, TypedAnswer};
+use crate::cards::CardUi;
+use crate::prelude::*;
+use rand::rngs::StdRng;
+
+/// Work out a sum. Deeper tiers bring in multiplication, then two steps at once.
+#[derive(Debug)]
+pub struct Arithmetic {
+    problem: String,
+    answer: String,
+    tier: usize,
+    input: TypedAnswer,
+}
+
+impl Arithmetic {
+    pub fn new(tier: usize, rng: &mut StdRng) -> Self {
+        let a: i32 = rng.gen_range(2..=12);
+        let b: i32 = rng.gen_range(2..=12);
+        let (problem, answer) = match (tier, rng.gen_range(0..3)) {
+            (_, 0) => (format!("{} + {} =", a * 3, b * 2), a * 3 + b * 2),
+            (0, _) | (_, 1) => (format!("{} - {} =", a * 3 + b, a), a * 2 + b),
+            (1, _) => (format!("{} x {} =", a, b), a * b),
+            _ => {
+                let c: i32 = rng.gen_range(2..=20);
+                (format!("{} x {} + {} =", a, b, c), a * b + c)
+            }
+        };
+        Arithmetic {
+            problem,
+            answer: answer.to_string(),
+            tier,
+            input: TypedAnswer::default(),
+        }
+    }
+}
+
+impl RobotChallenge for Arithmetic {
+    fn prefab(&self) -> &'static str {
+        "prefabs/robot_arithmetic_card.ron"
+    }
+
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<String> {
+        // Players tend to type "56 " or " 56", which still counts.
+        self.input
+            .handle_event(event, ui)
+            .map(|typed| typed.trim().to_string())
+    }
+
+    fn answer(&self) -> &str {
+        &self.answer
+    }
+
+    fn tier(&self) -> usize {
+        self.tier
+    }
+
+    fn retry(&mut self) {
+        self.input.clear();
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        self.input.render(ui);
+        set_text(ui, "arithmetic_problem", self.problem.clone());
+    }
+}
@@ -0,0 +1,136 @@
+use super::{set_text, RobotChallenge};
+use crate::balance::CaptchaTier;
+use crate::cards::CardUi;
+use crate::prelude::*;
+use rand::rngs::StdRng;
+
+const LIGHTS: [&str; 4] = ["memory_0", "memory_1", "memory_2", "memory_3"];
+const COLORS: [[f32; 3]; 4] = [
+    [1., 0.2, 0.2],
+    [0.2, 0.9, 0.2],
+    [0.3, 0.4, 1.],
+    [1., 0.85, 0.2],
+];
+const FLASH_TIME: f32 = 0.5; // Seconds each light stays lit.
+const STEP_TIME: f32 = 0.75; // Seconds from one light to the next.
+
+/// Watch the lights flash in order, then click them in the same order. Deeper tiers make the
+/// sequence longer: one light fewer than their captchas have characters, and never under three.
+#[derive(Debug)]
+pub struct Memory {
+    sequence: String, // Light indices, like "3102".
+    pressed: String,
+    tier: usize,
+    showing: Option<f32>, // Seconds into showing the sequence, until it's the player's turn.
+    flash: Option<(usize, f32)>, // The light the player last clicked, and how long it stays lit.
+}
+
+impl Memory {
+    pub fn new(tier: usize, settings: &CaptchaTier, rng: &mut StdRng) -> Self {
+        let length = settings.chars.max(4) - 1;
+        let sequence = (0..length)
+            .map(|_| std::char::from_digit(rng.gen_range(0..LIGHTS.len() as u32), 10))
+            .map(|digit| digit.expect("Unreachable: lights are single digits"))
+            .collect();
+        Memory {
+            sequence,
+            pressed: String::new(),
+            tier,
+            showing: Some(0.),
+            flash: None,
+        }
+    }
+
+    /// The light lit right now, if any.
+    fn lit(&self) -> Option<usize> {
+        match (self.showing, self.flash) {
+            (Some(elapsed), _) => {
+                let step = (elapsed / STEP_TIME) as usize;
+                let digit = self.sequence.chars().nth(step)?;
+                if elapsed % STEP_TIME < FLASH_TIME {
+                    digit.to_digit(10).map(|light| light as usize)
+                } else {
+                    None
+                }
+            }
+            (None, Some((light, _))) => Some(light),
+            (None, None) => None,
+        }
+    }
+}
+
+impl RobotChallenge for Memory {
+    fn prefab(&self) -> &'static str {
+        "prefabs/robot_memory_card.ron"
+    }
+
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<String> {
+        if event.event_type != UiEventType::Click || self.showing.is_some() {
+            return None;
+        }
+        let name = ui.name(event.target);
+        let light = LIGHTS.iter().position(|light| name.eq(light))?;
+        ui.sounds.robot_captcha_key();
+        self.flash = Some((light, FLASH_TIME));
+        self.pressed.push_str(&light.to_string());
+        if self.pressed.len() == self.sequence.len() {
+            Some(std::mem::take(&mut self.pressed))
+        } else {
+            None
+        }
+    }
+
+    fn answer(&self) -> &str {
+        &self.sequence
+    }
+
+    fn tier(&self) -> usize {
+        self.tier
+    }
+
+    fn retry(&mut self) {
+        self.pressed.clear();
+        self.flash = None;
+        self.showing = Some(0.);
+    }
+
+    fn update(&mut self, dt: f32) {
+        if let Some(elapsed) = self.showing.as_mut() {
+            *elapsed += dt;
+            if *elapsed >= STEP_TIME * self.sequence.len() as f32 {
+                self.showing = None;
+            }
+        }
+        if let Some((_, time_left)) = self.flash.as_mut() {
+            *time_left -= dt;
+            if *time_left <= 0. {
+                self.flash = None;
+            }
+        }
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        let lit = self.lit();
+        for (index, (id, [r, g, b])) in LIGHTS.iter().zip(COLORS.iter()).enumerate() {
+            let brightness = if lit == Some(index) { 1. } else { 0.35 };
+            if let Some(entity) = ui.find(id) {
+                ui.images
+                    .insert(
+                        entity,
+                        UiImage::SolidColor([r * brightness, g * brightness, b * brightness, 1.]),
+                    )
+                    .expect("Unreachable, entity exists");
+            }
+        }
+        let prompt = if self.showing.is_some() {
+            "Watch closely...".to_string()
+        } else {
+            format!(
+                "Your turn! {} of {}",
+                self.pressed.len(),
+                self.sequence.len()
+            )
+        };
+        set_text(ui, "memory_prompt", prompt);
+    }
+}
@@ -0,0 +1,108 @@
+use super::{set_text, RobotChallenge};
+use crate::cards::CardUi;
+use crate::prelude::*;
+use rand::rngs::StdRng;
+
+const SIDE: usize = 3;
+const SOLVED: [u8; SIDE * SIDE] = [1, 2, 3, 4, 5, 6, 7, 8, 0]; // 0 is the gap.
+const TILE: [f32; 4] = [0.55, 0.4, 0.25, 1.];
+const GAP: [f32; 4] = [0.1, 0.1, 0.1, 1.];
+
+/// Slide the numbered tiles back into order, gap last. Deeper tiers shuffle them further. The
+/// answer goes in by itself once the tiles are in order.
+#[derive(Debug)]
+pub struct Slider {
+    board: [u8; SIDE * SIDE],
+    answer: String,
+    tier: usize,
+}
+
+fn neighbours(index: usize) -> impl Iterator<Item = usize> {
+    let (row, column) = (index / SIDE, index % SIDE);
+    let up = row.checked_sub(1).map(|row| row * SIDE + column);
+    let down = Some(row + 1)
+        .filter(|row| *row < SIDE)
+        .map(|row| row * SIDE + column);
+    let left = column.checked_sub(1).map(|column| row * SIDE + column);
+    let right = Some(column + 1)
+        .filter(|column| *column < SIDE)
+        .map(|column| row * SIDE + column);
+    vec![up, down, left, right].into_iter().flatten()
+}
+
+fn text(board: &[u8]) -> String {
+    board.iter().map(|tile| tile.to_string()).collect()
+}
+
+impl Slider {
+    pub fn new(tier: usize, rng: &mut StdRng) -> Self {
+        // Shuffling by sliding keeps the puzzle solvable, which swapping tiles at random wouldn't.
+        let mut board = SOLVED;
+        let mut gap = SIDE * SIDE - 1;
+        let mut previous = gap;
+        let mut moves = 8 + 8 * tier.min(3);
+        while moves > 0 || board == SOLVED {
+            let options: Vec<usize> = neighbours(gap).filter(|next| *next != previous).collect();
+            let next = options[rng.gen_range(0..options.len())];
+            board.swap(gap, next);
+            previous = gap;
+            gap = next;
+            moves = moves.saturating_sub(1);
+        }
+        Slider {
+            board,
+            answer: text(&SOLVED),
+            tier,
+        }
+    }
+}
+
+impl RobotChallenge for Slider {
+    fn prefab(&self) -> &'static str {
+        "prefabs/robot_slider_card.ron"
+    }
+
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<String> {
+        if event.event_type != UiEventType::Click {
+            return None;
+        }
+        let name = ui.name(event.target);
+        let index: usize = name.strip_prefix("slide_")?.parse().ok()?;
+        if index >= self.board.len() {
+            return None;
+        }
+        let gap = neighbours(index).find(|next| self.board[*next] == 0)?;
+        ui.sounds.robot_captcha_key();
+        self.board.swap(index, gap);
+        if self.board == SOLVED {
+            Some(text(&self.board))
+        } else {
+            None
+        }
+    }
+
+    fn answer(&self) -> &str {
+        &self.answer
+    }
+
+    fn tier(&self) -> usize {
+        self.tier
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        for (index, tile) in self.board.iter().enumerate() {
+            let label = if *tile == 0 {
+                String::new()
+            } else {
+                tile.to_string()
+            };
+            set_text(ui, &format!("slide_{}_label", index), label);
+            if let Some(entity) = ui.find(&format!("slide_{}", index)) {
+                let color = if *tile == 0 { GAP } else { TILE };
+                ui.images
+                    .insert(entity, UiImage::SolidColor(color))
+                    .expect("Unreachable, entity exists");
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use super::{RobotChallenge, TypedAnswer};
+use crate::balance::{CaptchaTier, CaptchaValidation};
+use crate::captcha::CaptchaRequest;
+use crate::cards::CardUi;
+use crate::prelude::*;
+use amethyst::assets::Handle;
+use rand::rngs::StdRng;
+
+/// Type the text in a distorted image, drawn by the captcha pool. The Play Audio button reads the
+/// same text out instead.
+#[derive(Debug)]
+pub struct TextCaptcha {
+    request: CaptchaRequest,
+    captcha: Option<(Handle<Texture>, Option<SourceHandle>)>, // Once the pool has drawn it.
+    input: TypedAnswer,
+}
+
+impl TextCaptcha {
+    /// Draws the answer when the card opens, never the same as the last answer given.
+    pub fn new(
+        tier: usize,
+        style: &CaptchaTier,
+        digging: &DiggingStatus,
+        rng: &mut StdRng,
+    ) -> Self {
+        let last = digging
+            .captcha_attempts
+            .last()
+            .map(|attempt| attempt.answer.as_str());
+        // Heard rather than seen, a letter's case has to be said when it has to be typed.
+        let say_case = !digging.balance.captcha_validation.case_insensitive;
+        let request = loop {
+            let request = CaptchaRequest::draw(tier, style, say_case, rng);
+            if Some(request.answer.as_str()) != last {
+                break request;
+            }
+        };
+        TextCaptcha {
+            request,
+            captcha: None,
+            input: TypedAnswer::default(),
+        }
+    }
+}
+
+impl RobotChallenge for TextCaptcha {
+    fn prefab(&self) -> &'static str {
+        "prefabs/robot_card.ron"
+    }
+
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<String> {
+        if event.event_type == UiEventType::Click && ui.name(event.target).eq("play_audio") {
+            if let Some((_, Some(audio))) = &self.captcha {
+                ui.sounds.play_source(audio);
+            }
+            return None;
+        }
+        self.input.handle_event(event, ui)
+    }
+
+    fn answer(&self) -> &str {
+        match self.captcha {
+            Some(_) => &self.request.answer,
+            None => "",
+        }
+    }
+
+    fn tier(&self) -> usize {
+        self.request.tier
+    }
+
+    fn validation(&self, captchas: &CaptchaValidation) -> CaptchaValidation {
+        captchas.clone()
+    }
+
+    fn retry(&mut self) {
+        self.input.clear();
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        self.input.render(ui);
+        if self.captcha.is_none() {
+            // The card waits a frame or two for the worker to draw it.
+            self.captcha = ui
+                .captchas
+                .fetch(&self.request)
+                .map(|captcha| (captcha.texture, captcha.audio));
+        }
+        if let (Some((texture, _)), Some(entity)) = (&self.captcha, ui.find("captcha")) {
+            if matches!(ui.images.get(entity), Some(UiImage::SolidColor(_))) {
+                ui.images
+                    .insert(entity, UiImage::Texture(texture.clone()))
+                    .expect("Unreachable, entity exists");
+            }
+        }
+    }
+}
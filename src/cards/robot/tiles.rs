@@ -0,0 +1,123 @@
+use super::RobotChallenge;
+use crate::cards::CardUi;
+use crate::hole::{CRACKED_ROCK_SPRITE, DUG_OFFSET, ROCK_SPRITE};
+use crate::prelude::*;
+use rand::rngs::StdRng;
+
+const TILES: usize = 9;
+const SELECTED: [f32; 4] = [1., 0.85, 0.2, 1.];
+const UNSELECTED: [f32; 4] = [0.2, 0.2, 0.2, 1.];
+
+/// Pick out every dirt tile in a grid of dirt, rock and dug out ground. Deeper tiers hide more
+/// dirt among the rest.
+#[derive(Debug)]
+pub struct DirtTiles {
+    sprites: [usize; TILES],
+    answer: String, // The dirt tiles' indices, like "0,4,7".
+    selected: [bool; TILES],
+    tier: usize,
+    drawn: bool,
+}
+
+impl DirtTiles {
+    pub fn new(tier: usize, digging: &DiggingStatus, rng: &mut StdRng) -> Self {
+        let layers = &digging.balance.strata.layers;
+        let mut dirt: Vec<usize> = rand::seq::index::sample(rng, TILES, 3 + tier.min(2)).into_vec();
+        dirt.sort_unstable();
+        let mut sprites = [0; TILES];
+        for (index, sprite) in sprites.iter_mut().enumerate() {
+            let layer = layers[rng.gen_range(0..layers.len())].sprite;
+            *sprite = if dirt.contains(&index) {
+                layer
+            } else {
+                match rng.gen_range(0..3) {
+                    0 => ROCK_SPRITE,
+                    1 => CRACKED_ROCK_SPRITE,
+                    _ => layer + DUG_OFFSET,
+                }
+            };
+        }
+        DirtTiles {
+            sprites,
+            answer: join(dirt.into_iter()),
+            selected: [false; TILES],
+            tier,
+            drawn: false,
+        }
+    }
+}
+
+fn join(indices: impl Iterator<Item = usize>) -> String {
+    indices
+        .map(|index| index.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl RobotChallenge for DirtTiles {
+    fn prefab(&self) -> &'static str {
+        "prefabs/robot_tiles_card.ron"
+    }
+
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<String> {
+        if event.event_type != UiEventType::Click {
+            return None;
+        }
+        let name = ui.name(event.target);
+        if name.eq("solve_tiles") {
+            return Some(join((0..TILES).filter(|index| self.selected[*index])));
+        }
+        let index: usize = name.strip_prefix("tile_")?.parse().ok()?;
+        if let Some(selected) = self.selected.get_mut(index) {
+            ui.sounds.robot_captcha_key();
+            *selected = !*selected;
+        }
+        None
+    }
+
+    fn answer(&self) -> &str {
+        &self.answer
+    }
+
+    fn tier(&self) -> usize {
+        self.tier
+    }
+
+    fn retry(&mut self) {
+        self.selected = [false; TILES];
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        if !self.drawn {
+            let sheet = match ui.sprites {
+                Some(sprites) => sprites.tile_spritesheet.clone(),
+                None => return,
+            };
+            // The prefab's children may not be loaded yet, so keep at it until they all are.
+            let mut drawn = 0;
+            for (index, sprite_number) in self.sprites.iter().enumerate() {
+                if let Some(entity) = ui.find(&format!("tile_{}", index)) {
+                    drawn += 1;
+                    ui.images
+                        .insert(
+                            entity,
+                            UiImage::Sprite(SpriteRender {
+                                sprite_sheet: sheet.clone(),
+                                sprite_number: *sprite_number,
+                            }),
+                        )
+                        .expect("Unreachable, entity exists");
+                }
+            }
+            self.drawn = drawn == TILES;
+        }
+        for (index, selected) in self.selected.iter().enumerate() {
+            if let Some(entity) = ui.find(&format!("tile_frame_{}", index)) {
+                let color = if *selected { SELECTED } else { UNSELECTED };
+                ui.images
+                    .insert(entity, UiImage::SolidColor(color))
+                    .expect("Unreachable, entity exists");
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::{AlertState, Card, CardOutcome, CardUi};
+use crate::prelude::*;
+use crate::replay::ReplayAction;
+use crate::simulation::Simulation;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Upgrade {
+    ExtraBucket,
+    BiggerBuckets,
+    DrillTime,
+    RobotSpeed,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 4] = [
+        Upgrade::ExtraBucket,
+        Upgrade::BiggerBuckets,
+        Upgrade::DrillTime,
+        Upgrade::RobotSpeed,
+    ];
+
+    /// The shop card's buy button for this upgrade.
+    pub fn button(&self) -> &'static str {
+        match self {
+            Upgrade::ExtraBucket => "buy_extra_bucket",
+            Upgrade::BiggerBuckets => "buy_bigger_buckets",
+            Upgrade::DrillTime => "buy_drill_time",
+            Upgrade::RobotSpeed => "buy_robot_speed",
+        }
+    }
+
+    /// The shop card's label showing this upgrade's level and price.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Upgrade::ExtraBucket => "extra_bucket_cost",
+            Upgrade::BiggerBuckets => "bigger_buckets_cost",
+            Upgrade::DrillTime => "drill_time_cost",
+            Upgrade::RobotSpeed => "robot_speed_cost",
+        }
+    }
+
+    pub fn from_button(name: &str) -> Option<Upgrade> {
+        Upgrade::ALL
+            .iter()
+            .find(|upgrade| upgrade.button() == name)
+            .copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Upgrade::ExtraBucket => "More Buckets",
+            Upgrade::BiggerBuckets => "Bigger Buckets",
+            Upgrade::DrillTime => "Drill Fuel",
+            Upgrade::RobotSpeed => "Robot Oil",
+        }
+    }
+}
+
+/// How many times each upgrade has been bought this run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpgradeLevels {
+    pub extra_bucket: u32,
+    pub bigger_buckets: u32,
+    pub drill_time: u32,
+    pub robot_speed: u32,
+}
+
+impl UpgradeLevels {
+    pub fn level(&self, upgrade: Upgrade) -> u32 {
+        match upgrade {
+            Upgrade::ExtraBucket => self.extra_bucket,
+            Upgrade::BiggerBuckets => self.bigger_buckets,
+            Upgrade::DrillTime => self.drill_time,
+            Upgrade::RobotSpeed => self.robot_speed,
+        }
+    }
+
+    pub fn level_mut(&mut self, upgrade: Upgrade) -> &mut u32 {
+        match upgrade {
+            Upgrade::ExtraBucket => &mut self.extra_bucket,
+            Upgrade::BiggerBuckets => &mut self.bigger_buckets,
+            Upgrade::DrillTime => &mut self.drill_time,
+            Upgrade::RobotSpeed => &mut self.robot_speed,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ShopState;
+
+pub fn open(
+    state: AlertState,
+    _digging: &DiggingStatus,
+    _rng: &mut GameRng,
+) -> Option<Box<dyn Card>> {
+    match state {
+        AlertState::Shop => Some(Box::new(ShopState)),
+        _ => None,
+    }
+}
+
+impl Card for ShopState {
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<ReplayAction> {
+        if event.event_type != UiEventType::Click {
+            return None;
+        }
+        Upgrade::from_button(&ui.name(event.target)).map(ReplayAction::Buy)
+    }
+
+    fn act(
+        &mut self,
+        action: &ReplayAction,
+        simulation: &mut Simulation<'_>,
+        _rng: &mut GameRng,
+    ) -> CardOutcome {
+        match action {
+            ReplayAction::Buy(upgrade) if simulation.buy(*upgrade) => {
+                CardOutcome::sound(Sound::EmptyBucket)
+            }
+            ReplayAction::Buy(_) => CardOutcome::sound(Sound::RobotCaptchaFail),
+            _ => CardOutcome::default(),
+        }
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        let digging = ui.digging;
+        if let Some(text) = ui.find("shop_dirt").and_then(|ent| ui.texts.get_mut(ent)) {
+            text.text = format!("Dirt: {}", digging.dirt);
+        }
+        for upgrade in Upgrade::ALL.iter() {
+            if let Some(text) = ui
+                .find(upgrade.label())
+                .and_then(|ent| ui.texts.get_mut(ent))
+            {
+                let level = digging.upgrades.level(*upgrade);
+                text.text = match digging.upgrade_cost(*upgrade) {
+                    Some(cost) => format!("{} ({}): {}", upgrade.name(), level, cost),
+                    None => format!("{} ({}): Maxed", upgrade.name(), level),
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use super::{AlertState, Card, CardOutcome, CardUi, ShovelAlertState};
+use crate::prelude::*;
+use crate::replay::ReplayAction;
+use crate::simulation::Simulation;
+
+#[derive(Debug)]
+pub struct ShovelState {
+    pub click_progress: f32, // How long has it been since we clicked? Used for animation, perhaps.
+}
+
+pub fn open(
+    state: AlertState,
+    _digging: &DiggingStatus,
+    _rng: &mut GameRng,
+) -> Option<Box<dyn Card>> {
+    match state {
+        AlertState::Shovel(ShovelAlertState::Ready) => {
+            Some(Box::new(ShovelState { click_progress: 0. }))
+        }
+        _ => None,
+    }
+}
+
+impl Card for ShovelState {
+    fn handle_event(&mut self, event: &UiEvent, ui: &mut CardUi<'_, '_>) -> Option<ReplayAction> {
+        if event.event_type == UiEventType::Click && ui.name(event.target).eq("shovel_dirt") {
+            Some(ReplayAction::ShovelDirt)
+        } else {
+            None
+        }
+    }
+
+    fn act(
+        &mut self,
+        action: &ReplayAction,
+        simulation: &mut Simulation<'_>,
+        _rng: &mut GameRng,
+    ) -> CardOutcome {
+        match action {
+            ReplayAction::ShovelDirt if !simulation.scoop() => {
+                CardOutcome::close(Some(Sound::Shovel))
+            }
+            ReplayAction::ShovelDirt => CardOutcome::sound(Sound::Shovel),
+            _ => CardOutcome::default(),
+        }
+    }
+
+    fn render(&mut self, ui: &mut CardUi<'_, '_>) {
+        let digging = ui.digging;
+        if let Some(image) = ui
+            .find("shovel_bucket")
+            .and_then(|ent| ui.images.get_mut(ent))
+        {
+            // The sheet has eight fill frames, however big the buckets are.
+            let scoops_per_bucket = digging.scoops_per_bucket();
+            let frame =
+                (digging.scoops_in_top_bucket() * 8 + scoops_per_bucket - 1) / scoops_per_bucket;
+            if frame == 0 {
+                update_texture(image, Some(0.25), Some(0.375), Some(0.125), Some(0.25))
+            } else {
+                update_texture(
+                    image,
+                    Some(0.125 * frame as f32 - 0.125),
+                    Some(0.125 * frame as f32),
+                    Some(0.25),
+                    Some(0.375),
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use amethyst::config::Config;
+use amethyst::utils::application_root_dir;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub seed: Option<u64>,
+    // Only ever given on the command line: play this replay back headless instead of the game.
+    #[serde(skip)]
+    pub replay: Option<PathBuf>,
+}
+
+impl GameConfig {
+    /// Loads `config/game.ron`, then applies any overrides given on the command line.
+    pub fn load_with_args(path: &Path) -> Self {
+        let mut config = <GameConfig as Config>::load(path).unwrap_or_else(|err| {
+            warn!("Could not load {:?}, using defaults: {}", path, err);
+            GameConfig::default()
+        });
+        let mut args = std::env::args().skip(1).peekable();
+        while let Some(arg) = args.next() {
+            // A flag missing its value leaves the next flag alone.
+            let mut value = || args.next_if(|next| !next.starts_with("--"));
+            match arg.as_ref() {
+                "--seed" => match value().map(|seed| seed.parse()) {
+                    Some(Ok(seed)) => config.seed = Some(seed),
+                    Some(Err(err)) => warn!("Bad --seed ({}), keeping seed {:?}", err, config.seed),
+                    None => warn!("--seed needs a number, keeping seed {:?}", config.seed),
+                },
+                "--replay" => match value() {
+                    Some(path) => config.replay = Some(PathBuf::from(path)),
+                    None => warn!("--replay needs a file to play back"),
+                },
+                _ => {}
+            }
+        }
+        config
+    }
+}
+
+/// Where a file the game keeps for the player lives: next to the game, like its config.
+pub fn user_file(name: &str) -> PathBuf {
+    application_root_dir()
+        .map(|root| root.join(name))
+        .unwrap_or_else(|_| PathBuf::from(name))
+}
+
+/// Loads one of the player's files, or None if there isn't one yet. One that can't be read is
+/// warned about as `what` and treated as missing, so a bad file never stops the game.
+pub fn load_user_file<T: Config>(path: &Path, what: &str) -> Option<T> {
+    if !path.exists() {
+        return None;
+    }
+    T::load(path)
+        .map_err(|err| warn!("Could not load {} {:?}: {}", what, path, err))
+        .ok()
+}
+
+/// Writes one of the player's files, warning as `what` if it can't.
+pub fn store_user_file<T: Config>(value: &T, path: &Path, what: &str) {
+    if let Err(err) = value.write(path) {
+        warn!("Could not write {} {:?}: {}", what, path, err);
+    }
+}
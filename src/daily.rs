@@ -0,0 +1,113 @@
+use crate::config::{load_user_file, store_user_file, user_file};
+use crate::leaderboard::format_date;
+use crate::modes::{Difficulty, GameMode, Modifier};
+use crate::rng::hash;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const SECONDS_PER_DAY: u64 = 86400;
+// Mixed with the day, so daily seeds don't line up with small hand-picked ones.
+const DAILY_SALT: u64 = 0x4449_4747_494E_4721;
+
+/// The day's challenge: a seed and a mode, the same for everyone playing that day.
+///
+/// Days follow the player's local date, so the challenge turns over at their midnight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyChallenge {
+    pub day: u32, // Days since the unix epoch.
+    pub seed: u64,
+    pub mode: GameMode,
+}
+
+/// Today's local date, in days since the unix epoch.
+pub fn today() -> u32 {
+    let local = Local::now().naive_local().timestamp() as u64;
+    (local / SECONDS_PER_DAY) as u32
+}
+
+impl DailyChallenge {
+    pub fn today() -> Self {
+        DailyChallenge::for_day(today())
+    }
+
+    pub fn for_day(day: u32) -> Self {
+        let seed = hash(DAILY_SALT, day as u64);
+        let difficulty = Difficulty::ALL[(hash(seed, 0) % Difficulty::ALL.len() as u64) as usize];
+        let mut mode = GameMode {
+            difficulty,
+            modifiers: Vec::new(),
+        };
+        // Each modifier turns up on about one day in three.
+        for (index, modifier) in Modifier::ALL.iter().enumerate() {
+            if hash(seed, index as u64 + 1) % 3 == 0 {
+                mode.toggle(*modifier);
+            }
+        }
+        DailyChallenge { day, seed, mode }
+    }
+
+    pub fn date(&self) -> String {
+        format_date(self.day as u64 * SECONDS_PER_DAY)
+    }
+}
+
+/// A day's best finish.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub day: u32,
+    pub time_played: f32,
+    pub attempts: u32,
+}
+
+/// Every daily challenge finished, kept apart from the leaderboard in `daily.ron`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyHistory {
+    pub results: Vec<DailyResult>, // Oldest first.
+}
+
+pub fn daily_path() -> PathBuf {
+    user_file("daily.ron")
+}
+
+impl DailyHistory {
+    pub fn load() -> DailyHistory {
+        load_user_file(&daily_path(), "daily history").unwrap_or_default()
+    }
+
+    pub fn store(&self) {
+        store_user_file(self, &daily_path(), "daily history");
+    }
+
+    pub fn result(&self, day: u32) -> Option<&DailyResult> {
+        self.results.iter().find(|result| result.day == day)
+    }
+
+    /// Adds a finish of `day`'s challenge, keeping the day's best time.
+    pub fn record(&mut self, day: u32, time_played: f32) {
+        match self.results.iter_mut().find(|result| result.day == day) {
+            Some(result) => {
+                result.attempts += 1;
+                result.time_played = result.time_played.min(time_played);
+            }
+            None => {
+                self.results.push(DailyResult {
+                    day,
+                    time_played,
+                    attempts: 1,
+                });
+                self.results.sort_by_key(|result| result.day);
+            }
+        }
+    }
+
+    /// How many days in a row, ending at `day`, have a finished challenge.
+    pub fn streak(&self, day: u32) -> u32 {
+        let mut streak = 0;
+        while day >= streak && self.result(day - streak).is_some() {
+            streak += 1;
+        }
+        streak
+    }
+}
@@ -1,24 +1,23 @@
+use crate::balance::{Balance, BalanceWatcher, CaptchaVerdict, Unlock, MAX_BUCKETS};
+use crate::cards::{AlertState, ToolId, Upgrade, UpgradeLevels};
+use crate::modes::{GameMode, Modifier};
 use crate::prelude::*;
+use crate::profile::PrestigeBonuses;
+use crate::simulation::{Simulation, SimulationEvent};
+use crate::strata::Stratum;
 use amethyst::renderer::Camera;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
-pub const SCOOPS_PER_BLOCK: u32 = 4;
-pub const SCOOPS_PER_METER: u32 = 28;
-pub const BLOCKS_PER_METER: u32 = SCOOPS_PER_METER / SCOOPS_PER_BLOCK;
-pub const DRILL_METER: u32 = 20;
-pub const DRILL_TIME: f32 = 60.;
-pub const DRILL_SPEED: f32 = 4.;
-pub const ROBOT_METER: u32 = 40;
-pub const ROBOT_TIME: f32 = 60.;
-pub const ROBOT_SPEED: f32 = 0.25;
-
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum DrillStatus {
     Locked,
     Idling,
     Running { time_left: f32, partial_scoops: f32 },
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RobotStatus {
     Locked,
     Idling,
@@ -28,22 +27,47 @@ pub enum RobotStatus {
     },
 }
 
+/// An answer given to a robot, kept for the run's stats.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptchaAttempt {
+    pub time: f32, // Seconds into the run.
+    pub typed: String,
+    pub answer: String,
+    pub verdict: CaptchaVerdict,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DiggingStatus {
     scoops: u32,
     scoops_per_bucket: u32,
     pub time_since_shovel: f32,
     buckets: u32,
     pub depth: u32,
+    partial_depth: f32, // Scoops dug into harder ground that don't add up to a whole one yet.
     progression: u32,
-    progress_checks: u32,
+    pub meter_times: Vec<f32>, // Seconds into the run each meter was passed, meter 1 first.
+    pub rock_seed: u64,        // Where the rocks are. Set from the run's seed when it starts.
+    pub rock_hits: u32,        // Pickaxe swings into the current rock block so far.
     pub drill_status: DrillStatus,
-    pub robot_status: RobotStatus,
+    pub robots: Vec<RobotStatus>, // One per robot unlock, in unlock order.
     pub drill_pulls: u32,
     pub drills_started: u32,
     pub scoops_shoveled: u32,
     pub captchas_solved: u32,
+    pub captcha_attempts: Vec<CaptchaAttempt>, // Every answer given, right or wrong.
+    pub buckets_emptied: u32,
+    pub dirt: u32, // Scoops hauled out in buckets, spent in the shop.
+    pub upgrades: UpgradeLevels,
+    pub bonuses: PrestigeBonuses, // From the profile when the run started.
     pub time_played: f32,
     pub game_over: bool,
+    pub endless: bool, // Keeps digging past victory_depth instead of ending the run.
+    pub mode: GameMode,
+    pub daily: Option<u32>, // The day of the daily challenge this run is, if it is one.
+    // Comes from config/balance.ron rather than the save, so tuning applies to old runs too.
+    #[serde(skip)]
+    pub balance: Balance,
 }
 
 #[derive(Component, Debug)]
@@ -55,62 +79,153 @@ pub struct Bucket {
 #[derive(Component, Debug)]
 #[storage(VecStorage)]
 pub struct Robot {
-    pub index: u32, // Which bucket are we.
+    pub index: usize, // Which robot are we.
 }
 
 impl Default for DiggingStatus {
     fn default() -> Self {
+        DiggingStatus::new(Balance::default())
+    }
+}
+
+impl DiggingStatus {
+    pub fn new(balance: Balance) -> Self {
         DiggingStatus {
             scoops: 0,
-            scoops_per_bucket: 8,
+            scoops_per_bucket: balance.scoops_per_bucket,
             time_since_shovel: 1.,
-            buckets: 5,
-            depth: 4,
+            buckets: balance.starting_buckets,
+            depth: balance.scoops_per_block,
+            partial_depth: 0.,
             progression: 0,
-            progress_checks: SCOOPS_PER_METER,
+            meter_times: Vec::new(),
+            rock_seed: 0,
+            rock_hits: 0,
             drill_status: DrillStatus::Locked,
-            robot_status: RobotStatus::Locked,
+            robots: vec![RobotStatus::Locked; balance.robot_count()],
             drill_pulls: 0,
             drills_started: 0,
             scoops_shoveled: 0,
             captchas_solved: 0,
+            captcha_attempts: Vec::new(),
+            buckets_emptied: 0,
+            dirt: 0,
+            upgrades: UpgradeLevels::default(),
+            bonuses: PrestigeBonuses::default(),
             time_played: 0.,
             game_over: false,
+            endless: false,
+            mode: GameMode::default(),
+            daily: None,
+            balance,
+        }
+    }
+
+    /// A new run in `mode`, on top of the tuning from `balance.ron`.
+    pub fn with_mode(base: &Balance, mode: GameMode) -> Self {
+        let (mode, balance) = mode.fitted(base);
+        DiggingStatus {
+            mode,
+            ..DiggingStatus::new(balance)
+        }
+    }
+
+    /// A loaded run, played under the balance currently in effect.
+    pub fn with_balance(self, balance: Balance) -> Self {
+        DiggingStatus { balance, ..self }
+    }
+
+    /// Starts a new run with the profile's permanent bonuses.
+    pub fn apply_bonuses(&mut self, bonuses: PrestigeBonuses) {
+        self.bonuses = bonuses;
+        if !self.mode.has(Modifier::OneBucket) {
+            self.buckets = (self.buckets + bonuses.starting_bucket).min(MAX_BUCKETS);
         }
     }
-}
 
-impl DiggingStatus {
     pub fn scoop(&mut self, shovel: bool) {
+        let hardness = self.stratum().hardness;
         if shovel {
             self.scoops_shoveled += 1;
-            self.depth += 4;
+            self.dig(self.balance.scoops_per_block as f32 / hardness);
             self.time_since_shovel = 0.;
             self.scoops += 1;
         } else {
-            self.depth += 1;
+            self.dig(1. / hardness);
+        }
+    }
+
+    fn dig(&mut self, scoops: f32) {
+        let block = self.block_index();
+        self.partial_depth += scoops;
+        let whole = self.partial_depth.floor();
+        self.depth += whole as u32;
+        self.partial_depth -= whole;
+        if self.block_index() != block {
+            // Swings only count against the rock they were aimed at.
+            self.rock_hits = 0;
+        }
+    }
+
+    pub fn stratum(&self) -> Stratum {
+        self.stratum_at(self.level())
+    }
+
+    /// The layer at `meter`. Endless runs keep generating layers past the configured ones.
+    pub fn stratum_at(&self, meter: u32) -> Stratum {
+        if self.endless {
+            self.balance.strata.endless_at(meter, self.rock_seed)
+        } else {
+            *self.balance.strata.at(meter)
         }
     }
 
     pub fn drill(&mut self) {
         self.drills_started += 1;
         self.drill_status = DrillStatus::Running {
-            time_left: DRILL_TIME,
+            time_left: self.drill_time(),
             partial_scoops: 0.,
         };
     }
 
-    pub fn solve_captcha(&mut self) {
-        self.captchas_solved += 1;
-        self.robot_status = RobotStatus::Running {
-            time_left: ROBOT_TIME,
-            partial_buckets: 0.,
-        };
+    /// The captcha tier robots ask for at the current depth.
+    pub fn captcha_tier(&self) -> usize {
+        self.balance.captcha_tier_at(self.level())
+    }
+
+    /// Seconds a robot runs for solving a captcha of `tier`.
+    pub fn robot_time(&self, tier: usize) -> f32 {
+        let scale = self
+            .balance
+            .captcha_tiers
+            .get(tier)
+            .map_or(1., |tier| tier.robot_time_scale);
+        self.balance.robot_time * scale
+    }
+
+    /// Sends a robot off for `credit` of the time solving a captcha of `tier` earns.
+    pub fn solve_captcha(&mut self, robot: usize, tier: usize, credit: f32) {
+        let time_left = self.robot_time(tier) * credit;
+        // Only a stale or edited replay names a robot the run doesn't have.
+        match self.robots.get_mut(robot) {
+            Some(status) => {
+                self.captchas_solved += 1;
+                *status = RobotStatus::Running {
+                    time_left,
+                    partial_buckets: 0.,
+                };
+            }
+            None => warn!("There is no robot {} to run", robot),
+        }
     }
 
     pub fn empty_bucket(&mut self) {
         if self.scoops > 0 {
-            self.scoops = self.scoops - self.scoops_per_bucket;
+            // Only a whole bucket's worth if there is one: Bigger Buckets can leave less.
+            let hauled = self.scoops.min(self.scoops_per_bucket);
+            self.scoops -= hauled;
+            self.buckets_emptied += 1;
+            self.dirt += hauled;
         }
     }
 
@@ -126,6 +241,90 @@ impl DiggingStatus {
         }
     }
 
+    pub fn scoops_per_bucket(&self) -> u32 {
+        self.scoops_per_bucket
+    }
+
+    pub fn drill_time(&self) -> f32 {
+        self.balance.drill_time
+            + self.balance.drill_time_step * self.upgrades.level(Upgrade::DrillTime) as f32
+    }
+
+    pub fn drill_speed(&self) -> f32 {
+        self.balance.drill_speed * self.stratum().drill_efficiency
+    }
+
+    /// Buckets per second for each robot, slowed by the longer trip up from deeper layers.
+    pub fn robot_speed(&self) -> f32 {
+        (self.balance.robot_speed
+            + self.balance.robot_speed_step * self.upgrades.level(Upgrade::RobotSpeed) as f32)
+            * (1. + self.balance.prestige_robot_speed * self.bonuses.robot_speed as f32)
+            / self.stratum().robot_trip
+    }
+
+    /// What the next level of an upgrade costs, or `None` once there are no more levels.
+    pub fn upgrade_cost(&self, upgrade: Upgrade) -> Option<u32> {
+        if upgrade == Upgrade::ExtraBucket
+            && (self.buckets >= MAX_BUCKETS || self.mode.has(Modifier::OneBucket))
+        {
+            return None;
+        }
+        let level = self.upgrades.level(upgrade);
+        Some(
+            (self.balance.upgrade_cost as f32 * self.balance.upgrade_cost_growth.powi(level as i32))
+                as u32,
+        )
+    }
+
+    pub fn buy(&mut self, upgrade: Upgrade) -> bool {
+        match self.upgrade_cost(upgrade) {
+            Some(cost) if cost <= self.dirt => {
+                self.dirt -= cost;
+                *self.upgrades.level_mut(upgrade) += 1;
+                match upgrade {
+                    Upgrade::ExtraBucket => self.buckets += 1,
+                    Upgrade::BiggerBuckets => {
+                        self.scoops_per_bucket += self.balance.bucket_size_step
+                    }
+                    // These are read from the levels whenever they're used.
+                    Upgrade::DrillTime | Upgrade::RobotSpeed => {}
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a block is rock. Positions only depend on `rock_seed`, so a run has the same
+    /// rocks however often it's loaded or replayed.
+    pub fn is_rock(&self, block: u32) -> bool {
+        let meter = block / self.balance.blocks_per_meter();
+        // No rock until the meter after the pickaxe unlocks, so the shovel can never get stuck.
+        match self.balance.unlock_meter(ToolId::Pickaxe) {
+            Some(pickaxe_meter) if meter > pickaxe_meter => {}
+            _ => return false,
+        }
+        let roll = crate::rng::hash_roll(self.rock_seed, block as u64);
+        roll < self.stratum_at(meter).rock_chance as f64
+    }
+
+    pub fn blocked_by_rock(&self) -> bool {
+        self.is_rock(self.block_index())
+    }
+
+    /// One pickaxe swing at the current block. Returns true if it broke the rock.
+    pub fn hit_rock(&mut self) -> bool {
+        self.rock_hits += 1;
+        if self.rock_hits < self.balance.pickaxe_hits {
+            return false;
+        }
+        self.rock_hits = 0;
+        let scoops_per_block = self.balance.scoops_per_block;
+        self.depth = (self.block_index() + 1) * scoops_per_block;
+        self.partial_depth = 0.;
+        true
+    }
+
     pub fn can_scoop(&self) -> bool {
         self.scoops < self.buckets * self.scoops_per_bucket
     }
@@ -135,31 +334,75 @@ impl DiggingStatus {
     }
 
     pub fn level(&self) -> u32 {
-        self.depth / SCOOPS_PER_METER
+        self.depth / self.balance.scoops_per_meter
     }
 
     pub fn block_index(&self) -> u32 {
-        self.depth / SCOOPS_PER_BLOCK
+        self.depth / self.balance.scoops_per_block
     }
 
     pub fn current_block(&self) -> u32 {
-        (self.depth % SCOOPS_PER_METER) / SCOOPS_PER_BLOCK
+        (self.depth % self.balance.scoops_per_meter) / self.balance.scoops_per_block
     }
 
     pub fn current_block_height(&self) -> u32 {
-        (self.depth % SCOOPS_PER_METER) % SCOOPS_PER_BLOCK
+        (self.depth % self.balance.scoops_per_meter) % self.balance.scoops_per_block
+    }
+
+    pub fn reached_victory(&self) -> bool {
+        !self.endless && self.depth > self.balance.victory_depth * self.balance.scoops_per_meter
     }
 
     pub fn get_depth_string(&self) -> String {
-        format!("{:.3}", self.depth as f32 / SCOOPS_PER_METER as f32)
+        format!(
+            "{:.3}",
+            self.depth as f32 / self.balance.scoops_per_meter as f32
+        )
+    }
+
+    pub fn progression(&self) -> u32 {
+        self.progression
     }
 
-    pub fn progress(&mut self) -> u32 {
-        if (self.depth - 4) >= (self.progression * self.progress_checks) + self.progress_checks {
-            self.progression = (self.depth - 4) / self.progress_checks;
-            self.progression
+    /// Returns the meters passed since the last call, as `(previous, reached)`, if any.
+    pub fn progress(&mut self) -> Option<(u32, u32)> {
+        let dug = self.depth.saturating_sub(self.balance.scoops_per_block);
+        let reached = dug / self.balance.scoops_per_meter;
+        if reached > self.progression {
+            let previous = self.progression;
+            self.progression = reached;
+            // The drill can pass several meters in a frame; they all get this frame's time.
+            self.meter_times.resize(reached as usize, self.time_played);
+            Some((previous, reached))
         } else {
-            0
+            None
+        }
+    }
+
+    /// Whether a prestige bonus hands this tool out from the start, whatever its meter.
+    pub fn unlocked_early(&self, unlock: &Unlock) -> bool {
+        unlock.tool == ToolId::Drill && self.bonuses.early_drill > 0
+    }
+
+    pub fn is_unlocked(&self, unlock: &Unlock) -> bool {
+        unlock.meter <= self.progression || self.unlocked_early(unlock)
+    }
+
+    pub fn unlock(&mut self, unlock: &Unlock) {
+        match (unlock.tool, unlock.state) {
+            (ToolId::Drill, _) if self.drill_status == DrillStatus::Locked => {
+                self.drill_status = DrillStatus::Idling;
+            }
+            (ToolId::Robot, AlertState::Robot(robot, _)) => {
+                // The balance may have gained robots since this run was saved.
+                if self.robots.len() <= robot {
+                    self.robots.resize(robot + 1, RobotStatus::Locked);
+                }
+                if self.robots[robot] == RobotStatus::Locked {
+                    self.robots[robot] = RobotStatus::Idling;
+                }
+            }
+            _ => {}
         }
     }
 }
@@ -275,7 +518,12 @@ impl<'s> System<'s> for RobotRenderSystem {
          Loop through alertables, update the UI based on the alertable state.
         */
         for (robot, mut image, entity) in (&robots, &mut images, &entities).join() {
-            match digging.robot_status {
+            match digging
+                .robots
+                .get(robot.index)
+                .copied()
+                .unwrap_or(RobotStatus::Locked)
+            {
                 RobotStatus::Locked | RobotStatus::Idling => {
                     if hidden.get(entity).is_none() {
                         hidden
@@ -306,7 +554,7 @@ impl<'s> System<'s> for ShovelTimingSystem {
     // Also needed: Components for UI, not sure what we'll use yet.
     type SystemData = (Write<'s, DiggingStatus>, Read<'s, Time>);
     fn run(&mut self, (mut digging, time): Self::SystemData) {
-        digging.time_since_shovel += time.delta_seconds();
+        Simulation::new(&mut digging).advance_shovel(time.delta_seconds());
     }
 }
 
@@ -316,25 +564,7 @@ impl<'s> System<'s> for DrillDiggingSystem {
     // Also needed: Components for UI, not sure what we'll use yet.
     type SystemData = (Write<'s, DiggingStatus>, Read<'s, Time>);
     fn run(&mut self, (mut digging, time): Self::SystemData) {
-        let mut scooped = false;
-        if let DrillStatus::Running {
-            time_left,
-            partial_scoops,
-        } = &mut digging.drill_status
-        {
-            *time_left -= time.delta_seconds();
-            *partial_scoops += DRILL_SPEED * time.delta_seconds();
-            if *partial_scoops > 1. {
-                *partial_scoops -= 1.;
-                scooped = true;
-            }
-            if *time_left < 0. {
-                digging.drill_status = DrillStatus::Idling;
-            }
-        }
-        if scooped {
-            digging.scoop(false);
-        }
+        Simulation::new(&mut digging).advance_drill(time.delta_seconds());
     }
 }
 
@@ -344,28 +574,11 @@ impl<'s> System<'s> for RobotRunningSystem {
     // Also needed: Components for UI, not sure what we'll use yet.
     type SystemData = (Write<'s, DiggingStatus>, Read<'s, Time>, SoundPlayer<'s>);
     fn run(&mut self, (mut digging, time, sounds): Self::SystemData) {
-        let mut dumped = false;
-        if !digging.no_buckets() {
-            if let RobotStatus::Running {
-                time_left,
-                partial_buckets,
-            } = &mut digging.robot_status
-            {
-                *time_left -= time.delta_seconds();
-                *partial_buckets += ROBOT_SPEED * time.delta_seconds();
-                if *partial_buckets > 1. {
-                    *partial_buckets -= 1.;
-                    dumped = true;
-                }
-                if *time_left < 0. {
-                    sounds.robot_captcha();
-                    digging.robot_status = RobotStatus::Idling;
-                }
+        for event in Simulation::new(&mut digging).advance_robot(time.delta_seconds()) {
+            if let SimulationEvent::RobotIdle(_) = event {
+                sounds.robot_captcha();
             }
         }
-        if dumped {
-            digging.empty_bucket();
-        }
     }
 }
 
@@ -376,59 +589,95 @@ impl<'s> System<'s> for ProgressionSystem {
     type SystemData = (
         Write<'s, DiggingStatus>,
         WriteStorage<'s, Alertable>,
+        WriteStorage<'s, Robot>,
         WidgetSpawner<'s>,
         SoundPlayer<'s>,
         Read<'s, Time>,
     );
-    fn run(&mut self, (mut digging, mut alertables, mut spawner, sounds, time): Self::SystemData) {
-        if !digging.game_over {
-            digging.time_played += time.delta_seconds();
-        }
-        match digging.progress() {
-            DRILL_METER => {
-                sounds.drill_unlock();
-                digging.drill_status = DrillStatus::Idling;
-                let alert_entity = spawner.spawn_ui_widget(
-                    "prefabs/drill_alertable.ron",
-                    Position { x: -64., y: -160. },
-                );
-                alertables
-                    .insert(
-                        alert_entity,
-                        crate::cards::Alertable {
-                            state: crate::cards::AlertState::Drill(
-                                crate::cards::DrillAlertState::Ready,
-                            ),
-                            clicked: false,
-                        },
-                    )
-                    .expect("Unreachable: entity just created");
-            }
-            ROBOT_METER => {
-                sounds.robot_unlock();
-                digging.robot_status = RobotStatus::Idling;
-                let alert_entity = spawner.spawn_ui_widget(
-                    "prefabs/robot_alertable.ron",
-                    Position { x: -64., y: -224. },
-                );
-                alertables
-                    .insert(
-                        alert_entity,
-                        crate::cards::Alertable {
-                            state: crate::cards::AlertState::Robot(
-                                crate::cards::RobotAlertState::CaptchaNeeded,
-                            ),
-                            clicked: false,
-                        },
-                    )
-                    .expect("Unreachable: entity just created");
+    fn run(
+        &mut self,
+        (mut digging, mut alertables, mut robots, mut spawner, sounds, time): Self::SystemData,
+    ) {
+        let mut simulation = Simulation::new(&mut digging);
+        simulation.advance_clock(time.delta_seconds());
+        for event in simulation.advance_progression() {
+            if let SimulationEvent::Unlocked(index) = event {
+                let unlock = &digging.balance.unlocks[index];
+                if let Some(sound) = unlock.sound {
+                    sounds.play(sound);
+                }
+                spawn_unlock_alertable(&mut spawner, &mut alertables, unlock);
+                // Robots the run started with already have sprites, but a reloaded unlock
+                // table can add more mid-run.
+                if let AlertState::Robot(index, _) = unlock.state {
+                    if !(&robots).join().any(|robot| robot.index == index) {
+                        spawn_robot(&mut spawner, &mut robots, index);
+                    }
+                }
             }
-            _ => {}
         }
     }
 }
 
-pub struct DiggingBundle;
+pub fn spawn_unlock_alertable(
+    spawner: &mut WidgetSpawner,
+    alertables: &mut WriteStorage<'_, Alertable>,
+    unlock: &Unlock,
+) {
+    let alert_entity = spawner.spawn_ui_widget(&unlock.prefab, unlock.position);
+    alertables
+        .insert(
+            alert_entity,
+            Alertable {
+                state: unlock.state,
+                clicked: false,
+            },
+        )
+        .expect("Unreachable: entity just created");
+}
+
+pub fn spawn_robot(
+    spawner: &mut WidgetSpawner,
+    robots: &mut WriteStorage<'_, Robot>,
+    index: usize,
+) {
+    let robot_entity = spawner.spawn_ui_widget(
+        "prefabs/robot.ron",
+        Position {
+            x: -112. - (index as f32 * 64.),
+            y: 48.,
+        },
+    );
+    robots
+        .insert(robot_entity, Robot { index })
+        .expect("Unreachable: entity just created");
+}
+
+pub struct BalanceReloadSystem {
+    watcher: BalanceWatcher,
+}
+
+impl<'s> System<'s> for BalanceReloadSystem {
+    type SystemData = (Write<'s, DiggingStatus>, Write<'s, Balance>, Read<'s, Time>);
+    fn run(&mut self, (mut digging, mut base, time): Self::SystemData) {
+        if let Some(balance) = self.watcher.poll(time.delta_seconds(), &base) {
+            let (mode, fitted) = digging.mode.fitted(&balance);
+            digging.mode = mode;
+            digging.balance = fitted;
+            *base = balance;
+        }
+    }
+}
+
+pub struct DiggingBundle {
+    balance_path: PathBuf,
+}
+
+impl DiggingBundle {
+    pub fn new(balance_path: PathBuf) -> Self {
+        DiggingBundle { balance_path }
+    }
+}
 
 impl SystemBundle<'_, '_> for DiggingBundle {
     fn build(
@@ -436,15 +685,34 @@ impl SystemBundle<'_, '_> for DiggingBundle {
         world: &mut World,
         dispatcher: &mut DispatcherBuilder<'_, '_>,
     ) -> Result<(), Error> {
-        world.insert(DiggingStatus::default());
+        let balance = Balance::load(&self.balance_path).map_err(|err| {
+            Error::from_string(format!("Bad balance in {:?}: {}", self.balance_path, err))
+        })?;
+        // The tuning as loaded, before a game mode adjusts it for a run.
+        world.insert(balance.clone());
+        world.insert(DiggingStatus::new(balance));
+        dispatcher.add(
+            BalanceReloadSystem {
+                watcher: BalanceWatcher::new(self.balance_path),
+            },
+            "balance_reload",
+            &[],
+        );
         dispatcher.add(DepthCameraSystem, "depth_camera", &[]);
+        dispatcher.add(
+            crate::hole::ChunkStreamingSystem,
+            "chunk_streaming",
+            &["depth_camera"],
+        );
         dispatcher.add(DepthRenderSystem, "depth_render", &[]);
         dispatcher.add(RobotRenderSystem, "robot_render", &[]);
         dispatcher.add(BucketRenderSystem, "bucket_render", &[]);
-        dispatcher.add(ProgressionSystem, "progression", &[]);
-        dispatcher.add(DrillDiggingSystem, "drill_digging", &[]);
-        dispatcher.add(RobotRunningSystem, "robot_running", &[]);
-        dispatcher.add(ShovelTimingSystem, "shovel_timing", &[]);
+        // These run after the cards have handled this frame's input, in the same order as
+        // `Simulation::tick`, so replays play back exactly.
+        dispatcher.add(ProgressionSystem, "progression", &["card_update"]);
+        dispatcher.add(DrillDiggingSystem, "drill_digging", &["progression"]);
+        dispatcher.add(RobotRunningSystem, "robot_running", &["drill_digging"]);
+        dispatcher.add(ShovelTimingSystem, "shovel_timing", &["robot_running"]);
         Ok(())
     }
 }
@@ -1,31 +1,33 @@
 use crate::assets::SpriteStorage;
 use crate::prelude::*;
 use amethyst::core::math::{Point3, Vector3};
+use amethyst::renderer::Camera;
 use amethyst::tiles::*;
 
-pub const VICTORY_DEPTH: u32 = 100;
 pub const TILE_SCREEN_SIZE: f32 = 64.;
+/// Meters of shaft in each tile-map chunk streamed in around the camera.
+pub const CHUNK_METERS: u32 = 20;
+/// The tile sheet's rock block, and the same block once the pickaxe has cracked it.
+pub const ROCK_SPRITE: usize = 13;
+pub const CRACKED_ROCK_SPRITE: usize = 14;
+/// How many sprites past a layer's dirt its dug out ground is.
+pub const DUG_OFFSET: usize = 4;
 
 #[derive(Default, Clone)]
-pub struct HoleTile;
+pub struct HoleTile {
+    first_row: u32, // The meter the chunk this tile is in starts at.
+}
 impl Tile for HoleTile {
     fn sprite(&self, point: Point3<u32>, world: &World) -> Option<usize> {
         let (digging,): (Read<DiggingStatus>,) = world.system_data();
-        let sprite_idx = if point.y < crate::digging::DRILL_METER {
-            0
-        } else if point.y < crate::digging::ROBOT_METER {
-            16
-        } else if point.y < VICTORY_DEPTH - 1 {
-            24
-        } else {
-            32
-        };
-        if point.y < digging.level() {
-            Some(sprite_idx + 4)
-        } else if point.y > digging.level() {
+        let row = self.first_row + point.y;
+        let sprite_idx = digging.stratum_at(row).sprite;
+        if row < digging.level() {
+            Some(sprite_idx + DUG_OFFSET)
+        } else if row > digging.level() {
             Some(sprite_idx)
         } else if point.x < digging.current_block() {
-            Some(sprite_idx + 4)
+            Some(sprite_idx + DUG_OFFSET)
         } else if point.x == digging.current_block() {
             Some(sprite_idx + digging.current_block_height() as usize)
         } else {
@@ -34,30 +36,41 @@ impl Tile for HoleTile {
     }
 
     fn tint(&self, coordinates: Point3<u32>, world: &World) -> Srgba {
-        Srgba::new(1.0, 1.0, 1.0, 1.0)
+        let (digging,): (Read<DiggingStatus>,) = world.system_data();
+        let (r, g, b, a) = digging.stratum_at(self.first_row + coordinates.y).tint;
+        Srgba::new(r, g, b, a)
     }
 }
 
 #[derive(Default, Clone)]
-pub struct SpriteTile;
+pub struct SpriteTile {
+    first_row: u32, // The meter the chunk this tile is in starts at.
+}
 impl Tile for SpriteTile {
     fn sprite(&self, point: Point3<u32>, world: &World) -> Option<usize> {
         let (digging,): (Read<DiggingStatus>,) = world.system_data();
         let block_index = digging.block_index();
-        let tile_index = point.y * BLOCKS_PER_METER + point.x;
-        if block_index > 0 && tile_index == block_index - 1 {
+        let blocks_per_meter = digging.balance.blocks_per_meter();
+        let tile_index = (self.first_row + point.y) * blocks_per_meter + point.x;
+        if tile_index >= block_index && digging.is_rock(tile_index) {
+            if tile_index == block_index && digging.rock_hits > 0 {
+                Some(CRACKED_ROCK_SPRITE)
+            } else {
+                Some(ROCK_SPRITE)
+            }
+        } else if block_index > 0 && tile_index == block_index - 1 {
             if digging.time_since_shovel < 0.125 {
                 Some(9)
             } else {
                 Some(8)
             }
-        } else if block_index > 1 && tile_index == block_index - 2 {
-            match digging.robot_status {
-                RobotStatus::Locked => None,
-                RobotStatus::Running { .. } => None,
-                RobotStatus::Idling => Some(12),
-            }
-        } else if block_index >= BLOCKS_PER_METER && tile_index == block_index - BLOCKS_PER_METER {
+        } else if tile_index + 1 < block_index
+            // Idle robots wait in a line behind the shovel, the first one closest.
+            && digging.robots.get((block_index - tile_index - 2) as usize)
+                == Some(&RobotStatus::Idling)
+        {
+            Some(12)
+        } else if block_index >= blocks_per_meter && tile_index == block_index - blocks_per_meter {
             match digging.drill_status {
                 DrillStatus::Locked => None,
                 DrillStatus::Running { .. } => Some(10),
@@ -69,36 +82,129 @@ impl Tile for SpriteTile {
     }
 }
 
+/// One chunk's worth of shaft. Each chunk is a pair of entities, the ground and the tools.
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct HoleChunk {
+    pub index: u32, // Starts at meter `index * CHUNK_METERS`.
+}
+
+/// Keeps the chunks of shaft around the camera spawned, and despawns the ones it has passed.
+///
+/// A normal run stops at `victory_depth`; an endless one keeps streaming deeper chunks in.
+pub struct ChunkStreamingSystem;
+
+impl<'s> System<'s> for ChunkStreamingSystem {
+    type SystemData = (
+        Read<'s, DiggingStatus>,
+        Option<Read<'s, SpriteStorage>>,
+        ReadStorage<'s, Camera>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, HoleChunk>,
+        WriteStorage<'s, TileMap<HoleTile, MortonEncoder2D>>,
+        WriteStorage<'s, TileMap<SpriteTile, MortonEncoder2D>>,
+        Entities<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (digging, sprites, cameras, mut transforms, mut chunks, mut holes, mut tools, entities): Self::SystemData,
+    ) {
+        let sprites = match sprites {
+            Some(sprites) => sprites,
+            None => return,
+        };
+        let camera_y = match (&cameras, &transforms).join().next() {
+            Some((_, transform)) => transform.translation().y,
+            None => return,
+        };
+        let camera_chunk = (camera_y.min(0.) / -TILE_SCREEN_SIZE) as u32 / CHUNK_METERS;
+        let victory_depth = digging.balance.victory_depth;
+        let mut last = camera_chunk + 1;
+        if !digging.endless {
+            last = last.min((victory_depth.max(1) - 1) / CHUNK_METERS);
+        }
+        let wanted = camera_chunk.saturating_sub(1)..=last;
+
+        let mut spawned = Vec::new();
+        for (chunk, entity) in (&chunks, &entities).join() {
+            if wanted.contains(&chunk.index) {
+                spawned.push(chunk.index);
+            } else {
+                entities
+                    .delete(entity)
+                    .expect("Unreachable, entity definitely exists");
+            }
+        }
+
+        let blocks_per_meter = digging.balance.blocks_per_meter();
+        for index in wanted.filter(|index| !spawned.contains(index)) {
+            let first_row = index * CHUNK_METERS;
+            let rows = if digging.endless {
+                CHUNK_METERS
+            } else {
+                CHUNK_METERS.min(victory_depth - first_row)
+            };
+            let dimensions = Vector3::<u32>::new(blocks_per_meter, rows, 1);
+            let mut hole = TileMap::<HoleTile, MortonEncoder2D>::new(
+                dimensions,
+                Vector3::<u32>::new(32, 32, 1),
+                Some(sprites.tile_spritesheet.clone()),
+            );
+            let mut tool = TileMap::<SpriteTile, MortonEncoder2D>::new(
+                dimensions,
+                Vector3::<u32>::new(32, 32, 1),
+                Some(sprites.tile_spritesheet.clone()),
+            );
+            for y in 0..rows {
+                for x in 0..blocks_per_meter {
+                    let point = Point3::new(x, y, 0);
+                    if let Some(tile) = hole.get_mut(&point) {
+                        tile.first_row = first_row;
+                    }
+                    if let Some(tile) = tool.get_mut(&point) {
+                        tile.first_row = first_row;
+                    }
+                }
+            }
+
+            let mut transform = Transform::default();
+            transform.set_translation_x(32.);
+            transform.set_translation_y(
+                -(first_row as f32 * TILE_SCREEN_SIZE) - rows as f32 * TILE_SCREEN_SIZE / 2.,
+            );
+            transform.set_translation_z(0.2);
+            transform.set_scale(Vector3::new(2., 2., 1.));
+            let hole_entity = entities.create();
+            holes
+                .insert(hole_entity, hole)
+                .expect("Unreachable, entity just created");
+            transforms
+                .insert(hole_entity, transform.clone())
+                .expect("Unreachable, entity just created");
+            chunks
+                .insert(hole_entity, HoleChunk { index })
+                .expect("Unreachable, entity just created");
+
+            transform.set_translation_z(0.5);
+            let tool_entity = entities.create();
+            tools
+                .insert(tool_entity, tool)
+                .expect("Unreachable, entity just created");
+            transforms
+                .insert(tool_entity, transform)
+                .expect("Unreachable, entity just created");
+            chunks
+                .insert(tool_entity, HoleChunk { index })
+                .expect("Unreachable, entity just created");
+        }
+    }
+}
+
+/// Spawns the fixtures around the shaft. The shaft itself is streamed in by
+/// `ChunkStreamingSystem`.
 pub fn spawn_hole(world: &mut World) {
-    let tilesheet = world
-        .read_resource::<SpriteStorage>()
-        .tile_spritesheet
-        .clone();
     let master = world.read_resource::<SpriteStorage>().master.clone();
-    let mut transform = Transform::default();
-    transform.set_translation_x(32.);
-    transform.set_translation_y(VICTORY_DEPTH as f32 * -32.);
-    transform.set_translation_z(0.2);
-    transform.set_scale(Vector3::new(2., 2., 1.));
-    world
-        .create_entity()
-        .with(TileMap::<HoleTile, MortonEncoder2D>::new(
-            Vector3::<u32>::new(BLOCKS_PER_METER, VICTORY_DEPTH, 1),
-            Vector3::<u32>::new(32, 32, 1),
-            Some(tilesheet.clone()),
-        ))
-        .with(transform.clone())
-        .build();
-    transform.set_translation_z(0.5);
-    world
-        .create_entity()
-        .with(TileMap::<SpriteTile, MortonEncoder2D>::new(
-            Vector3::<u32>::new(BLOCKS_PER_METER, VICTORY_DEPTH, 1),
-            Vector3::<u32>::new(32, 32, 1),
-            Some(tilesheet),
-        ))
-        .with(transform)
-        .build();
     let mut fixture_transform = Transform::default();
     // Left border
     fixture_transform.set_translation_x(-512. + 32.);
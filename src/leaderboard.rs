@@ -0,0 +1,169 @@
+use crate::config::{load_user_file, store_user_file, user_file};
+use crate::digging::DiggingStatus;
+use crate::modes::GameMode;
+use crate::save::now;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::PathBuf;
+
+// The game over screen has this many leaderboard rows, and each mode keeps this many runs.
+pub const TOP_RUNS: usize = 5;
+
+/// One finished run, as the leaderboard remembers it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub finished_at: u64, // Seconds since the unix epoch.
+    pub seed: u64,
+    pub time_played: f32,
+    pub drill_pulls: u32,
+    pub captchas_solved: u32,
+    pub scoops_shoveled: u32,
+    #[serde(default)]
+    pub mode: GameMode,
+}
+
+impl RunRecord {
+    pub fn new(digging: &DiggingStatus, seed: u64) -> Self {
+        RunRecord {
+            finished_at: now(),
+            seed,
+            time_played: digging.time_played,
+            drill_pulls: digging.drill_pulls,
+            captchas_solved: digging.captchas_solved,
+            scoops_shoveled: digging.scoops_shoveled,
+            mode: digging.mode.clone(),
+        }
+    }
+}
+
+/// Which records the last finished run set, for the game over screen to point out.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NewBests {
+    pub rank: Option<usize>, // Where the run placed among the fastest, if it made the list.
+    pub fastest: bool,
+    pub fewest_drill_pulls: bool,
+    pub most_captchas: bool,
+}
+
+/// The fastest finished runs and the per-category records, kept in `leaderboard.ron`.
+///
+/// Runs only compete with runs in the same mode, so each mode has its own list and records.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Leaderboard {
+    pub fastest: Vec<RunRecord>, // Best first, at most TOP_RUNS per mode.
+    pub fewest_drill_pulls: Vec<RunRecord>, // One per mode.
+    pub most_captchas: Vec<RunRecord>, // One per mode.
+    #[serde(skip)]
+    pub last: NewBests,
+}
+
+pub fn leaderboard_path() -> PathBuf {
+    user_file("leaderboard.ron")
+}
+
+impl Leaderboard {
+    pub fn load() -> Leaderboard {
+        load_user_file(&leaderboard_path(), "leaderboard").unwrap_or_default()
+    }
+
+    pub fn store(&self) {
+        store_user_file(self, &leaderboard_path(), "leaderboard");
+    }
+
+    /// The fastest runs in `mode`, best first.
+    pub fn fastest_in<'a>(&'a self, mode: &'a GameMode) -> impl Iterator<Item = &'a RunRecord> {
+        self.fastest.iter().filter(move |run| run.mode == *mode)
+    }
+
+    pub fn fewest_drill_pulls_in(&self, mode: &GameMode) -> Option<&RunRecord> {
+        self.fewest_drill_pulls.iter().find(|run| run.mode == *mode)
+    }
+
+    pub fn most_captchas_in(&self, mode: &GameMode) -> Option<&RunRecord> {
+        self.most_captchas.iter().find(|run| run.mode == *mode)
+    }
+
+    /// Adds a finished run, remembering which records it set in `last`.
+    pub fn record(&mut self, run: RunRecord) {
+        let rank = self
+            .fastest_in(&run.mode)
+            .position(|other| run.time_played < other.time_played)
+            .unwrap_or_else(|| self.fastest_in(&run.mode).count());
+        let mut last = NewBests::default();
+        if rank < TOP_RUNS {
+            self.fastest.push(run.clone());
+            self.fastest.sort_by(|a, b| {
+                a.time_played
+                    .partial_cmp(&b.time_played)
+                    .unwrap_or(Ordering::Equal)
+            });
+            let mut kept = 0;
+            self.fastest.retain(|other| {
+                if other.mode != run.mode {
+                    return true;
+                }
+                kept += 1;
+                kept <= TOP_RUNS
+            });
+            last.rank = Some(rank);
+            last.fastest = rank == 0;
+        }
+        last.fewest_drill_pulls = replace_record(&mut self.fewest_drill_pulls, &run, |best| {
+            run.drill_pulls < best.drill_pulls
+        });
+        last.most_captchas = replace_record(&mut self.most_captchas, &run, |best| {
+            run.captchas_solved > best.captchas_solved
+        });
+        self.last = last;
+    }
+}
+
+/// Swaps in `run` as its mode's record if it beats the old one. Returns whether it did.
+fn replace_record(
+    records: &mut Vec<RunRecord>,
+    run: &RunRecord,
+    beats: impl Fn(&RunRecord) -> bool,
+) -> bool {
+    match records.iter_mut().find(|best| best.mode == run.mode) {
+        Some(best) if beats(best) => {
+            *best = run.clone();
+            true
+        }
+        Some(_) => false,
+        None => {
+            records.push(run.clone());
+            true
+        }
+    }
+}
+
+/// Seconds as `m:ss.s`, or `h:mm:ss` once past an hour.
+pub fn format_time(seconds: f32) -> String {
+    let whole = seconds.max(0.) as u32;
+    if whole >= 3600 {
+        format!("{}:{:02}:{:02}", whole / 3600, whole / 60 % 60, whole % 60)
+    } else {
+        format!("{}:{:04.1}", whole / 60, seconds.max(0.) % 60.)
+    }
+}
+
+/// A unix timestamp as a `yyyy-mm-dd` date, in UTC.
+pub fn format_date(timestamp: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm.
+    let days = (timestamp / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -1,8 +1,10 @@
 #![windows_subsystem = "windows"]
 use crate::cards::CardsBundle;
+use crate::config::GameConfig;
 use crate::digging::DiggingBundle;
 use crate::hole::{HoleTile, SpriteTile};
 use crate::prelude::DjSystem;
+use crate::rng::GameRng;
 use crate::widgets::WidgetPositioningSystem;
 use amethyst::tiles::RenderTiles2D;
 use amethyst::{
@@ -19,13 +21,27 @@ use amethyst::{
     utils::application_root_dir,
 };
 
+mod achievements;
 mod assets;
+mod audio_captcha;
+mod balance;
 mod captcha;
 mod cards;
+mod config;
+mod daily;
 mod digging;
 mod hole;
+mod leaderboard;
+mod modes;
 mod prelude;
+mod profile;
+mod replay;
+mod rng;
+mod save;
+mod simulation;
+mod splits;
 mod state;
+mod strata;
 mod widgets;
 
 fn main() -> amethyst::Result<()> {
@@ -36,6 +52,11 @@ fn main() -> amethyst::Result<()> {
     let resources = app_root.join("assets");
     let display_config = app_root.join("config/display_config.ron");
     let key_bindings_path = app_root.join("config/input.ron");
+    let achievements = achievements::Achievements::load(&app_root.join("config/achievements.ron"));
+    let game_config = GameConfig::load_with_args(&app_root.join("config/game.ron"));
+    if let Some(replay_path) = game_config.replay {
+        return replay::run_headless(&replay_path);
+    }
 
     let game_data = GameDataBuilder::default()
         .with_bundle(TransformBundle::new())?
@@ -55,12 +76,28 @@ fn main() -> amethyst::Result<()> {
         )?
         .with_bundle(AudioBundle::default())?
         .with_bundle(CardsBundle)?
-        .with_bundle(DiggingBundle)?
+        .with_bundle(DiggingBundle::new(app_root.join("config/balance.ron")))?
         .with(DjSystem, "dj", &[])
         .with(crate::state::EndGameRenderer, "endgame", &[])
+        .with(crate::state::AwaySummaryRenderer, "away_summary", &[])
+        .with(crate::save::AutosaveSystem::default(), "autosave", &[])
+        .with(
+            crate::achievements::AchievementSystem::default(),
+            "achievements",
+            &[],
+        )
+        .with(crate::splits::SplitTimerSystem, "split_timer", &[])
+        .with(crate::captcha::CaptchaPoolSystem, "captcha_pool", &[])
         .with(WidgetPositioningSystem, "widget_pos", &[]);
 
-    let mut game = Application::new(resources, state::LoadingState::new(), game_data)?;
+    let mut game = Application::build(resources, state::LoadingState::new())?
+        .with_resource(GameRng::new(game_config.seed))
+        .with_resource(captcha::CaptchaPool::start())
+        .with_resource(profile::Profile::load())
+        .with_resource(achievements)
+        .with_resource(leaderboard::Leaderboard::load())
+        .with_resource(daily::DailyHistory::load())
+        .build(game_data)?;
     game.run();
 
     Ok(())
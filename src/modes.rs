@@ -0,0 +1,229 @@
+use crate::balance::{Balance, MAX_BUCKETS};
+use crate::cards::ToolId;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+// Seconds a robot runs per captcha with the Captcha Rush modifier.
+pub const CAPTCHA_RUSH_TIME: f32 = 30.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// The mode select screen's button for this difficulty.
+    pub fn button(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn from_button(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL
+            .iter()
+            .find(|difficulty| difficulty.button() == name)
+            .copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// Multiplies the victory depth, and stretches the strata and unlocks to match.
+    fn depth_scale(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 1.5,
+        }
+    }
+
+    /// Multiplies how long the drill and robots run per start.
+    fn timer_scale(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 0.75,
+        }
+    }
+
+    fn extra_buckets(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 2,
+            Difficulty::Normal | Difficulty::Hard => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Modifier {
+    NoDrill,
+    CaptchaRush,
+    OneBucket,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 3] = [
+        Modifier::NoDrill,
+        Modifier::CaptchaRush,
+        Modifier::OneBucket,
+    ];
+
+    /// The mode select screen's toggle button for this modifier.
+    pub fn button(&self) -> &'static str {
+        match self {
+            Modifier::NoDrill => "mod_no_drill",
+            Modifier::CaptchaRush => "mod_captcha_rush",
+            Modifier::OneBucket => "mod_one_bucket",
+        }
+    }
+
+    /// The mode select screen's label showing whether this modifier is on.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Modifier::NoDrill => "no_drill_state",
+            Modifier::CaptchaRush => "captcha_rush_state",
+            Modifier::OneBucket => "one_bucket_state",
+        }
+    }
+
+    pub fn from_button(name: &str) -> Option<Modifier> {
+        Modifier::ALL
+            .iter()
+            .find(|modifier| modifier.button() == name)
+            .copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Modifier::NoDrill => "No Drill",
+            Modifier::CaptchaRush => "Captcha Rush",
+            Modifier::OneBucket => "One Bucket",
+        }
+    }
+}
+
+/// The difficulty and modifiers a run is played with. Runs are only compared against runs in
+/// the same mode.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameMode {
+    pub difficulty: Difficulty,
+    pub modifiers: Vec<Modifier>, // Kept sorted, so equal modes compare equal.
+}
+
+impl GameMode {
+    pub fn has(&self, modifier: Modifier) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+
+    pub fn toggle(&mut self, modifier: Modifier) {
+        if self.has(modifier) {
+            self.modifiers.retain(|other| *other != modifier);
+        } else {
+            self.modifiers.push(modifier);
+            self.modifiers.sort();
+        }
+    }
+
+    /// For showing the player, like `Hard, No Drill`.
+    pub fn name(&self) -> String {
+        let mut parts = vec![self.difficulty.name()];
+        parts.extend(self.modifiers.iter().map(|modifier| modifier.name()));
+        parts.join(", ")
+    }
+
+    /// For file names, like `hard-no_drill`.
+    pub fn slug(&self) -> String {
+        let mut parts = vec![self.difficulty.button()];
+        parts.extend(
+            self.modifiers
+                .iter()
+                .map(|modifier| modifier.button().trim_start_matches("mod_")),
+        );
+        parts.join("-")
+    }
+
+    /// The tuning a run in this mode plays with, from the tuning in `balance.ron`, or why the
+    /// mode doesn't fit it.
+    pub fn apply(&self, base: &Balance) -> Result<Balance, String> {
+        let mut balance = base.clone();
+        let scale = self.difficulty.depth_scale();
+        if (scale - 1.).abs() > f32::EPSILON {
+            let depth = ((base.victory_depth as f32 * scale).round() as u32).max(2);
+            balance.victory_depth = depth;
+            balance.strata = base.strata.stretched(scale);
+            for unlock in balance.unlocks.iter_mut().filter(|unlock| unlock.meter > 0) {
+                unlock.meter = ((unlock.meter as f32 * scale).round() as u32)
+                    .max(1)
+                    .min(depth - 1);
+            }
+            for tier in balance.captcha_tiers.iter_mut() {
+                tier.meter = (tier.meter as f32 * scale).round() as u32;
+            }
+        }
+        // Shorter digs finish sooner, so par and the reward for finishing shrink with the depth.
+        balance.prestige_par_time = base.prestige_par_time * scale;
+        balance.prestige_per_run = (base.prestige_per_run as f32 * scale).round() as u32;
+        let timers = self.difficulty.timer_scale();
+        balance.drill_time *= timers;
+        balance.robot_time *= timers;
+        balance.starting_buckets =
+            (base.starting_buckets + self.difficulty.extra_buckets()).min(MAX_BUCKETS);
+        if self.has(Modifier::NoDrill) {
+            balance
+                .unlocks
+                .retain(|unlock| unlock.tool != ToolId::Drill);
+        }
+        if self.has(Modifier::CaptchaRush) {
+            // Every pass runs the robot for exactly this long, whatever the tier or a near miss.
+            balance.robot_time = CAPTCHA_RUSH_TIME;
+            for tier in balance.captcha_tiers.iter_mut() {
+                tier.robot_time_scale = 1.;
+            }
+            if balance.captcha_validation.partial_credit > 0. {
+                balance.captcha_validation.partial_credit = 1.;
+            }
+        }
+        if self.has(Modifier::OneBucket) {
+            balance.starting_buckets = 1;
+        }
+        balance.validate()?;
+        Ok(balance)
+    }
+
+    /// The mode a run is played and recorded as, with its balance. A mode that doesn't fit `base`
+    /// falls back to the default, so its leaderboard and splits never list a run it didn't shape.
+    pub fn fitted(&self, base: &Balance) -> (GameMode, Balance) {
+        match self.apply(base) {
+            Ok(balance) => (self.clone(), balance),
+            Err(err) => {
+                let fallback = GameMode::default();
+                warn!(
+                    "{} doesn't fit this balance ({}), so the run plays as {}",
+                    self.name(),
+                    err,
+                    fallback.name()
+                );
+                (fallback, base.clone())
+            }
+        }
+    }
+}
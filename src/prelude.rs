@@ -1,9 +1,8 @@
 pub use crate::assets::{SoundStorage, SpriteStorage};
-pub use crate::captcha::CaptchaData;
+pub use crate::captcha::{CaptchaData, CaptchaPool};
 pub use crate::cards::Alertable;
-pub use crate::digging::{
-    DiggingStatus, DrillStatus, RobotStatus, BLOCKS_PER_METER, SCOOPS_PER_BLOCK, SCOOPS_PER_METER,
-};
+pub use crate::digging::{DiggingStatus, DrillStatus, RobotStatus};
+pub use crate::rng::{GameRng, RngStream};
 pub use crate::widgets::*;
 pub use amethyst::{
     assets::{AssetStorage, PrefabData},
@@ -27,6 +26,7 @@ pub use amethyst::{
     },
 };
 pub use rand::{random, thread_rng, Rng};
+use serde::{Deserialize, Serialize};
 pub use shrev::EventChannel;
 
 pub fn get_ui_name(
@@ -69,6 +69,22 @@ pub fn update_texture(
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Sound {
+    Shovel,
+    EmptyBucket,
+    DrillSpin,
+    DrillStart,
+    DrillUnlock,
+    RobotCaptcha,
+    RobotCaptchaKey,
+    RobotCaptchaFail,
+    RobotCaptchaSuccess,
+    RobotUnlock,
+    PickaxeHit,
+    PickaxeBreak,
+}
+
 #[derive(SystemData)]
 pub struct SoundPlayer<'a> {
     storage: Option<Read<'a, SoundStorage>>,
@@ -167,6 +183,48 @@ impl<'a> SoundPlayer<'a> {
             }
         }
     }
+    pub fn pickaxe_hit(&self) {
+        if let Some(ref output) = self.output.as_ref() {
+            if let Some(ref sounds) = self.storage.as_ref() {
+                if let Some(sound) = self.sources.get(&sounds.pickaxe_hit.clone()) {
+                    output.play_once(sound, 1.);
+                }
+            }
+        }
+    }
+    pub fn pickaxe_break(&self) {
+        if let Some(ref output) = self.output.as_ref() {
+            if let Some(ref sounds) = self.storage.as_ref() {
+                if let Some(sound) = self.sources.get(&sounds.pickaxe_break.clone()) {
+                    output.play_once(sound, 1.);
+                }
+            }
+        }
+    }
+    pub fn play(&self, sound: Sound) {
+        match sound {
+            Sound::Shovel => self.shovel(),
+            Sound::EmptyBucket => self.empty_bucket(),
+            Sound::DrillSpin => self.drill_spin(),
+            Sound::DrillStart => self.drill_start(),
+            Sound::DrillUnlock => self.drill_unlock(),
+            Sound::RobotCaptcha => self.robot_captcha(),
+            Sound::RobotCaptchaKey => self.robot_captcha_key(),
+            Sound::RobotCaptchaFail => self.robot_captcha_fail(),
+            Sound::RobotCaptchaSuccess => self.robot_captcha_success(),
+            Sound::RobotUnlock => self.robot_unlock(),
+            Sound::PickaxeHit => self.pickaxe_hit(),
+            Sound::PickaxeBreak => self.pickaxe_break(),
+        }
+    }
+    /// Plays a sound that isn't one of the game's own, like an audio captcha.
+    pub fn play_source(&self, source: &SourceHandle) {
+        if let Some(ref output) = self.output.as_ref() {
+            if let Some(sound) = self.sources.get(source) {
+                output.play_once(sound, 1.);
+            }
+        }
+    }
     pub fn play_main_theme(&self, sink: &amethyst::audio::AudioSink) {
         if let Some(ref sounds) = self.storage.as_ref() {
             if let Some(sound) = self.sources.get(&sounds.main_theme.clone()) {
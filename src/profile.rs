@@ -0,0 +1,152 @@
+use crate::balance::{Balance, MAX_BUCKETS};
+use crate::config::{load_user_file, store_user_file, user_file};
+use crate::digging::DiggingStatus;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Permanent bonuses bought with prestige, carried into every new run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Bonus {
+    StartingBucket,
+    RobotSpeed,
+    EarlyDrill,
+}
+
+impl Bonus {
+    pub const ALL: [Bonus; 3] = [Bonus::StartingBucket, Bonus::RobotSpeed, Bonus::EarlyDrill];
+
+    /// The game over screen's buy button for this bonus.
+    pub fn button(&self) -> &'static str {
+        match self {
+            Bonus::StartingBucket => "prestige_starting_bucket",
+            Bonus::RobotSpeed => "prestige_robot_speed",
+            Bonus::EarlyDrill => "prestige_early_drill",
+        }
+    }
+
+    /// The game over screen's label showing this bonus's level and price.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Bonus::StartingBucket => "starting_bucket_cost",
+            Bonus::RobotSpeed => "prestige_robot_speed_cost",
+            Bonus::EarlyDrill => "early_drill_cost",
+        }
+    }
+
+    pub fn from_button(name: &str) -> Option<Bonus> {
+        Bonus::ALL
+            .iter()
+            .find(|bonus| bonus.button() == name)
+            .copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bonus::StartingBucket => "Spare Bucket",
+            Bonus::RobotSpeed => "Robot Tune-up",
+            Bonus::EarlyDrill => "Drill Permit",
+        }
+    }
+
+    /// Levels past this can't be bought.
+    pub fn max_level(&self, balance: &Balance) -> u32 {
+        match self {
+            Bonus::StartingBucket => MAX_BUCKETS.saturating_sub(balance.starting_buckets),
+            Bonus::RobotSpeed => 10,
+            Bonus::EarlyDrill => 1,
+        }
+    }
+}
+
+/// How many times each bonus has been bought, over every run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrestigeBonuses {
+    pub starting_bucket: u32,
+    pub robot_speed: u32,
+    pub early_drill: u32,
+}
+
+impl PrestigeBonuses {
+    pub fn level(&self, bonus: Bonus) -> u32 {
+        match bonus {
+            Bonus::StartingBucket => self.starting_bucket,
+            Bonus::RobotSpeed => self.robot_speed,
+            Bonus::EarlyDrill => self.early_drill,
+        }
+    }
+
+    pub fn level_mut(&mut self, bonus: Bonus) -> &mut u32 {
+        match bonus {
+            Bonus::StartingBucket => &mut self.starting_bucket,
+            Bonus::RobotSpeed => &mut self.robot_speed,
+            Bonus::EarlyDrill => &mut self.early_drill,
+        }
+    }
+}
+
+/// Everything that outlasts a run, kept in `profile.ron` next to the save.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub prestige: u32,
+    pub runs_completed: u32,
+    pub bonuses: PrestigeBonuses,
+    pub achievements: Vec<String>, // Ids of the achievements earned.
+    // What the last finished run earned, for the game over screen.
+    #[serde(skip)]
+    pub last_earned: u32,
+}
+
+pub fn profile_path() -> PathBuf {
+    user_file("profile.ron")
+}
+
+/// Prestige for finishing a run: more for finishing quickly, plus some for the work put in.
+pub fn prestige_earned(digging: &DiggingStatus) -> u32 {
+    let balance = &digging.balance;
+    let pace = (balance.prestige_par_time / digging.time_played.max(1.)).min(3.);
+    let earned = balance.prestige_per_run as f32 * pace
+        + balance.prestige_per_captcha * digging.captchas_solved as f32
+        + balance.prestige_per_drill * digging.drills_started as f32;
+    earned.round() as u32
+}
+
+impl Profile {
+    pub fn load() -> Profile {
+        load_user_file(&profile_path(), "profile").unwrap_or_default()
+    }
+
+    pub fn store(&self) {
+        store_user_file(self, &profile_path(), "profile");
+    }
+
+    /// Banks the prestige for a finished run.
+    pub fn award(&mut self, digging: &DiggingStatus) {
+        self.last_earned = prestige_earned(digging);
+        self.prestige += self.last_earned;
+        self.runs_completed += 1;
+    }
+
+    /// What the next level of a bonus costs, or `None` once it's maxed.
+    pub fn cost(&self, bonus: Bonus, balance: &Balance) -> Option<u32> {
+        let level = self.bonuses.level(bonus);
+        if level >= bonus.max_level(balance) {
+            return None;
+        }
+        Some(
+            (balance.prestige_cost as f32 * balance.prestige_cost_growth.powi(level as i32)) as u32,
+        )
+    }
+
+    pub fn buy(&mut self, bonus: Bonus, balance: &Balance) -> bool {
+        match self.cost(bonus, balance) {
+            Some(cost) if cost <= self.prestige => {
+                self.prestige -= cost;
+                *self.bonuses.level_mut(bonus) += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use crate::balance::Balance;
+use crate::cards::{AlertState, Card, CardRegistry, Upgrade};
+use crate::config::user_file;
+use crate::digging::DiggingStatus;
+use crate::rng::GameRng;
+use crate::simulation::Simulation;
+use amethyst::config::Config;
+use amethyst::core::timing::Time;
+use amethyst::ecs::{Read, System, Write};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReplayAction {
+    OpenCard(AlertState),
+    ShovelDirt,
+    BucketHoldStart,
+    BucketHoldStop,
+    PullDrill,
+    CaptchaCommit {
+        #[serde(default)]
+        robot: usize,
+        typed: String,
+        answer: String,
+        #[serde(default)]
+        tier: usize, // The captcha's, which sets how long the robot runs.
+    },
+    Buy(Upgrade),
+    SwingPickaxe,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedAction {
+    pub frame: usize,
+    pub time: f32,
+    pub action: ReplayAction,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub balance: Balance,
+    pub initial_status: DiggingStatus,
+    pub timesteps: Vec<f32>,
+    pub actions: Vec<RecordedAction>,
+    pub final_status: Option<DiggingStatus>,
+}
+
+pub fn replay_directory() -> PathBuf {
+    user_file("replays")
+}
+
+#[derive(Default)]
+pub struct ReplayRecorder {
+    replay: Replay,
+    recording: bool,
+}
+
+impl ReplayRecorder {
+    pub fn start(&mut self, seed: u64, digging: &DiggingStatus) {
+        self.replay = Replay {
+            seed,
+            balance: digging.balance.clone(),
+            initial_status: digging.clone(),
+            ..Replay::default()
+        };
+        self.recording = true;
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        if self.recording {
+            self.replay.timesteps.push(dt);
+        }
+    }
+
+    pub fn record(&mut self, time: f32, action: ReplayAction) {
+        if self.recording {
+            self.replay.actions.push(RecordedAction {
+                frame: self.replay.timesteps.len().saturating_sub(1),
+                time,
+                action,
+            });
+        }
+    }
+
+    /// Stops recording and writes the run out, named after when it was saved and its seed.
+    pub fn finish(&mut self, digging: &DiggingStatus, directory: &Path) {
+        if !self.recording {
+            return;
+        }
+        self.recording = false;
+        self.replay.final_status = Some(digging.clone());
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let path = directory.join(format!("run-{}-{}.ron", stamp, self.replay.seed));
+        if let Err(err) = std::fs::create_dir_all(directory) {
+            warn!("Could not create {:?}: {}", directory, err);
+        } else if let Err(err) = self.replay.write(&path) {
+            warn!("Could not write replay {:?}: {}", path, err);
+        } else {
+            info!("Wrote replay to {:?}", path);
+        }
+    }
+}
+
+/// Notes every frame's timestep, so the replay advances by exactly the same amounts.
+pub struct ReplayFrameSystem;
+
+impl<'s> System<'s> for ReplayFrameSystem {
+    type SystemData = (Write<'s, ReplayRecorder>, Read<'s, Time>);
+
+    fn run(&mut self, (mut recorder, time): Self::SystemData) {
+        recorder.step(time.delta_seconds());
+    }
+}
+
+/// Plays a replay back headless, through the same cards the game opens but without their widgets,
+/// in the order the dispatcher runs the card and digging systems. Returns where the run ended up.
+pub fn play_back(replay: &Replay) -> DiggingStatus {
+    let mut digging = replay
+        .initial_status
+        .clone()
+        .with_balance(replay.balance.clone());
+    let mut rng = GameRng::new(Some(replay.seed));
+    let registry = CardRegistry::default();
+    let mut card: Option<Box<dyn Card>> = None;
+    let mut actions = replay.actions.iter().peekable();
+    for (frame, dt) in replay.timesteps.iter().enumerate() {
+        // Cards delete their entity when they're done, which only takes effect at the end of
+        // the frame, so later input that frame still lands on them.
+        let mut closed = false;
+        while let Some(recorded) = actions.next_if(|recorded| recorded.frame == frame) {
+            match (&recorded.action, &mut card) {
+                (ReplayAction::OpenCard(state), _) => {
+                    card = registry
+                        .open(*state, &digging, &mut rng)
+                        .map(|(_, card)| card);
+                    closed = false;
+                }
+                (action, Some(card)) => {
+                    let mut simulation = Simulation::new(&mut digging);
+                    closed |= card.act(action, &mut simulation, &mut rng).close;
+                }
+                (action, None) => {
+                    warn!("Frame {}: {:?} has no card to act on", frame, action);
+                }
+            }
+        }
+        let mut simulation = Simulation::new(&mut digging);
+        if let Some(open) = &mut card {
+            closed |= open.update(&mut simulation, *dt).close;
+        }
+        if closed {
+            card = None;
+        }
+        simulation.tick(*dt);
+    }
+    digging
+}
+
+/// Loads a replay file, plays it back and reports whether it ends where the recording did.
+pub fn run_headless(path: &Path) -> amethyst::Result<()> {
+    let replay = <Replay as Config>::load(path)?;
+    info!(
+        "Replaying {:?}: seed {}, {} frames, {} actions",
+        path,
+        replay.seed,
+        replay.timesteps.len(),
+        replay.actions.len()
+    );
+    // The balance is stored alongside the run, not in it, so only the run itself is compared.
+    let digging = play_back(&replay).with_balance(Balance::default());
+    match &replay.final_status {
+        Some(recorded) if *recorded == digging => {
+            info!("Replay matches the recorded run: {:?}", digging);
+        }
+        Some(recorded) => {
+            warn!(
+                "Replay diverged!\nRecorded: {:?}\nReplayed: {:?}",
+                recorded, digging
+            );
+        }
+        None => {
+            info!("Replay finished: {:?}", digging);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{BucketAlertState, DrillAlertState, RobotAlertState, ShovelAlertState};
+
+    const SEED: u64 = 1234;
+    const DT: f32 = 1. / 60.;
+
+    /// What players do over a short run, by frame. Whether the card systems really act in the
+    /// order `play_back` assumes takes the game itself to check; this is only the recording.
+    fn script(frame: usize, tier: usize) -> Option<ReplayAction> {
+        let commit = |typed: &str| ReplayAction::CaptchaCommit {
+            robot: 0,
+            typed: typed.to_string(),
+            answer: "abcde".to_string(),
+            tier,
+        };
+        match frame {
+            0 => Some(ReplayAction::OpenCard(AlertState::Shovel(
+                ShovelAlertState::Ready,
+            ))),
+            1..=40 => Some(ReplayAction::ShovelDirt),
+            50 => Some(ReplayAction::OpenCard(AlertState::Bucket(
+                BucketAlertState::Filled(1.),
+            ))),
+            51 => Some(ReplayAction::BucketHoldStart),
+            200 => Some(ReplayAction::BucketHoldStop),
+            210 => Some(ReplayAction::OpenCard(AlertState::Drill(
+                DrillAlertState::Ready,
+            ))),
+            211 => Some(ReplayAction::PullDrill),
+            500 | 600 => Some(ReplayAction::OpenCard(AlertState::Robot(
+                0,
+                RobotAlertState::CaptchaNeeded,
+            ))),
+            501 => Some(commit("wrong")),
+            601 => Some(commit("abcde")),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn recorded_run_round_trips_through_its_file() {
+        // At 41m, so every tool up to the first robot is already unlocked.
+        let mut digging = DiggingStatus::new(Balance::default());
+        digging.rock_seed = SEED;
+        digging.depth = digging.balance.scoops_per_block + 41 * digging.balance.scoops_per_meter;
+        Simulation::new(&mut digging).advance_progression();
+        let tier = digging.captcha_tier();
+
+        let mut recorder = ReplayRecorder::default();
+        recorder.start(SEED, &digging);
+        for frame in 0..900 {
+            recorder.step(DT);
+            if let Some(action) = script(frame, tier) {
+                recorder.record(frame as f32 * DT, action);
+            }
+        }
+        let finished = play_back(&recorder.replay);
+
+        let directory = std::env::temp_dir().join(format!("replay-test-{}", std::process::id()));
+        recorder.finish(&finished, &directory);
+        let path = std::fs::read_dir(&directory)
+            .expect("the replay directory was written")
+            .next()
+            .expect("the replay was written")
+            .expect("the replay can be listed")
+            .path();
+        let loaded = <Replay as Config>::load(&path).expect("the replay reads back");
+        std::fs::remove_dir_all(&directory).expect("the replay can be cleaned up");
+
+        assert_eq!(loaded, recorder.replay);
+        assert_eq!(play_back(&loaded), finished);
+    }
+}
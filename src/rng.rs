@@ -0,0 +1,76 @@
+use rand::rngs::StdRng;
+use rand::{random, SeedableRng};
+
+#[derive(Debug, Clone, Copy)]
+pub enum RngStream {
+    Reels,
+    Captchas,
+}
+
+/// Every random decision in a run draws from here, so a seed reproduces the whole run.
+///
+/// Each kind of decision gets its own stream, so that opening an extra captcha doesn't change
+/// how the drill reels spin later on.
+pub struct GameRng {
+    fixed_seed: Option<u64>,
+    seed: u64,
+    reels: StdRng,
+    captchas: StdRng,
+}
+
+impl GameRng {
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        let seed = fixed_seed.unwrap_or_else(random);
+        GameRng {
+            fixed_seed,
+            seed,
+            reels: StdRng::seed_from_u64(seed),
+            captchas: StdRng::seed_from_u64(seed.wrapping_add(1)),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Rewinds every stream to the start of the given seed.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = GameRng {
+            fixed_seed: self.fixed_seed,
+            ..GameRng::new(Some(seed))
+        };
+    }
+
+    /// Starts the streams for a new run: the configured seed again if there is one, otherwise a
+    /// fresh one.
+    pub fn next_run(&mut self) {
+        self.reseed(self.fixed_seed.unwrap_or_else(random));
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut StdRng {
+        match stream {
+            RngStream::Reels => &mut self.reels,
+            RngStream::Captchas => &mut self.captchas,
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(None)
+    }
+}
+
+/// splitmix64 of `seed` and `n`: a well mixed number that only depends on the two.
+pub fn hash(seed: u64, n: u64) -> u64 {
+    let mut hash = seed.wrapping_add((n + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    hash ^ (hash >> 31)
+}
+
+/// A roll in [0, 1) that depends only on `seed` and `n`, for things like rock positions that
+/// have to come out the same whatever order they're looked at in.
+pub fn hash_roll(seed: u64, n: u64) -> f64 {
+    (hash(seed, n) >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,92 @@
+use crate::config::{load_user_file, store_user_file, user_file};
+use crate::digging::DiggingStatus;
+use crate::rng::GameRng;
+use amethyst::core::timing::Time;
+use amethyst::ecs::{Read, System};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const AUTOSAVE_INTERVAL: f32 = 30.;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub saved_at: u64, // Seconds since the unix epoch.
+    pub seed: u64,
+    pub digging: DiggingStatus,
+}
+
+pub fn save_path() -> PathBuf {
+    user_file("save.ron")
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+impl SaveGame {
+    pub fn new(digging: &DiggingStatus, seed: u64) -> Self {
+        SaveGame {
+            saved_at: now(),
+            seed,
+            digging: digging.clone(),
+        }
+    }
+
+    pub fn exists() -> bool {
+        save_path().exists()
+    }
+
+    pub fn load() -> Option<SaveGame> {
+        load_user_file(&save_path(), "save")
+    }
+
+    pub fn store(&self) {
+        store_user_file(self, &save_path(), "save");
+    }
+
+    pub fn delete() {
+        let path = save_path();
+        if path.exists() {
+            if let Err(err) = std::fs::remove_file(&path) {
+                warn!("Could not delete save {:?}: {}", path, err);
+            }
+        }
+    }
+}
+
+/// Whether there is a run in progress worth saving. Only `GameplayState` turns this on.
+#[derive(Default)]
+pub struct Autosave {
+    pub enabled: bool,
+}
+
+#[derive(Default)]
+pub struct AutosaveSystem {
+    elapsed: f32,
+}
+
+impl<'s> System<'s> for AutosaveSystem {
+    type SystemData = (
+        Read<'s, Autosave>,
+        Read<'s, DiggingStatus>,
+        Read<'s, GameRng>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (autosave, digging, rng, time): Self::SystemData) {
+        if !autosave.enabled || digging.game_over {
+            self.elapsed = 0.;
+            return;
+        }
+        self.elapsed += time.delta_seconds();
+        if self.elapsed > AUTOSAVE_INTERVAL {
+            self.elapsed = 0.;
+            SaveGame::new(&digging, rng.seed()).store();
+        }
+    }
+}
@@ -0,0 +1,509 @@
+use crate::balance::{Balance, CaptchaValidation, CaptchaVerdict};
+use crate::cards::{BucketState, DrillState, Upgrade};
+use crate::digging::{CaptchaAttempt, DiggingStatus, DrillStatus, RobotStatus};
+use crate::rng::{GameRng, RngStream};
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimulationEvent {
+    Unlocked(usize), // Index into the balance's unlock table.
+    DrillStopped,
+    RobotIdle(usize),
+    BucketEmptied,
+}
+
+// How finely time away is stepped through when catching up a loaded save.
+pub const CATCH_UP_STEP: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AwaySummary {
+    pub elapsed: f32,
+    pub scoops: u32,
+    pub buckets: u32,
+}
+
+/// The rules of the dig, without any engine in the way.
+///
+/// The ECS systems are thin adapters over this: they hand it the frame time and the player's
+/// actions, then play sounds and spawn widgets for whatever events come back out.
+pub struct Simulation<'a> {
+    digging: &'a mut DiggingStatus,
+}
+
+impl<'a> Simulation<'a> {
+    pub fn new(digging: &'a mut DiggingStatus) -> Self {
+        Simulation { digging }
+    }
+
+    /// Advances everything that runs on a timer, in the same order the dispatcher runs the
+    /// adapter systems, so a headless run matches a windowed one frame for frame.
+    pub fn tick(&mut self, dt: f32) -> Vec<SimulationEvent> {
+        let mut events = Vec::new();
+        self.advance_clock(dt);
+        events.extend(self.advance_progression());
+        events.extend(self.advance_drill(dt));
+        events.extend(self.advance_robot(dt));
+        self.advance_shovel(dt);
+        events
+    }
+
+    pub fn advance_clock(&mut self, dt: f32) {
+        if !self.digging.game_over {
+            self.digging.time_played += dt;
+        }
+    }
+
+    pub fn advance_shovel(&mut self, dt: f32) {
+        self.digging.time_since_shovel += dt;
+    }
+
+    pub fn advance_drill(&mut self, dt: f32) -> Option<SimulationEvent> {
+        let speed = self.digging.drill_speed();
+        let mut scooped = false;
+        let mut stopped = false;
+        if let DrillStatus::Running {
+            time_left,
+            partial_scoops,
+        } = &mut self.digging.drill_status
+        {
+            *time_left -= dt;
+            *partial_scoops += speed * dt;
+            if *partial_scoops > 1. {
+                *partial_scoops -= 1.;
+                scooped = true;
+            }
+            stopped = *time_left < 0.;
+        }
+        if stopped {
+            self.digging.drill_status = DrillStatus::Idling;
+        }
+        // Rock stops the drill as it does the shovel: it runs on the spot until the pickaxe
+        // breaks through.
+        if scooped && !self.digging.blocked_by_rock() {
+            self.digging.scoop(false);
+        }
+        if stopped {
+            Some(SimulationEvent::DrillStopped)
+        } else {
+            None
+        }
+    }
+
+    pub fn advance_robot(&mut self, dt: f32) -> Vec<SimulationEvent> {
+        let mut events = Vec::new();
+        for robot in 0..self.digging.robots.len() {
+            if self.digging.no_buckets() {
+                break;
+            }
+            let speed = self.digging.robot_speed();
+            let mut dumped = false;
+            let mut idle = false;
+            if let RobotStatus::Running {
+                time_left,
+                partial_buckets,
+            } = &mut self.digging.robots[robot]
+            {
+                *time_left -= dt;
+                *partial_buckets += speed * dt;
+                if *partial_buckets > 1. {
+                    *partial_buckets -= 1.;
+                    dumped = true;
+                }
+                idle = *time_left < 0.;
+            }
+            if idle {
+                self.digging.robots[robot] = RobotStatus::Idling;
+                events.push(SimulationEvent::RobotIdle(robot));
+            }
+            if dumped {
+                self.digging.empty_bucket();
+            }
+        }
+        events
+    }
+
+    /// Applies every unlock passed since the last check, even several in one go.
+    pub fn advance_progression(&mut self) -> Vec<SimulationEvent> {
+        let mut events = Vec::new();
+        if let Some((previous, reached)) = self.digging.progress() {
+            for index in 0..self.digging.balance.unlocks.len() {
+                let unlock = &self.digging.balance.unlocks[index];
+                if unlock.meter > previous
+                    && unlock.meter <= reached
+                    && !self.digging.unlocked_early(unlock)
+                {
+                    let unlock = unlock.clone();
+                    self.digging.unlock(&unlock);
+                    events.push(SimulationEvent::Unlocked(index));
+                }
+            }
+        }
+        events
+    }
+
+    /// Runs the drill and robot for time spent away from the game, as far as their timers go.
+    pub fn catch_up(&mut self, elapsed: f32) -> AwaySummary {
+        let depth = self.digging.depth;
+        let buckets_emptied = self.digging.buckets_emptied;
+        let drill_left = match self.digging.drill_status {
+            DrillStatus::Running { time_left, .. } => time_left,
+            _ => 0.,
+        };
+        let robot_left = self
+            .digging
+            .robots
+            .iter()
+            .map(|robot| match robot {
+                RobotStatus::Running { time_left, .. } => *time_left,
+                _ => 0.,
+            })
+            .fold(0., f32::max);
+        let mut remaining = elapsed.min(drill_left.max(robot_left));
+        while remaining > 0. {
+            let dt = remaining.min(CATCH_UP_STEP);
+            self.advance_drill(dt);
+            self.advance_robot(dt);
+            remaining -= dt;
+        }
+        AwaySummary {
+            elapsed,
+            scoops: self.digging.depth - depth,
+            buckets: self.digging.buckets_emptied - buckets_emptied,
+        }
+    }
+
+    /// Scoops one shovel of dirt. Returns whether the shovel can go again, which it can't once
+    /// the buckets are full or it hits rock.
+    pub fn scoop(&mut self) -> bool {
+        if !self.digging.blocked_by_rock() {
+            self.digging.scoop(true);
+        }
+        self.digging.can_scoop() && !self.digging.blocked_by_rock()
+    }
+
+    /// Swings the pickaxe at the current block. `None` if it isn't rock, otherwise whether the
+    /// swing broke it.
+    pub fn swing_pickaxe(&mut self) -> Option<bool> {
+        if self.digging.blocked_by_rock() {
+            Some(self.digging.hit_rock())
+        } else {
+            None
+        }
+    }
+
+    pub fn pull_cord(&mut self, drill: &mut DrillState, rng: &mut GameRng) {
+        let reels = rng.stream(RngStream::Reels);
+        let velocity = (
+            reels.gen::<f32>() * 10.,
+            reels.gen::<f32>() * 10.,
+            reels.gen::<f32>() * 10.,
+        );
+        let position = match drill {
+            DrillState::Idling(a, b, c)
+            | DrillState::Running {
+                position: (a, b, c),
+                ..
+            } => (*a, *b, *c),
+        };
+        self.digging.drill_pulls += 1;
+        *drill = DrillState::Running { position, velocity };
+    }
+
+    /// Spins the drill reels down. Returns true once they stop on a start, which starts the drill.
+    pub fn spin_reels(&mut self, drill: &mut DrillState, dt: f32) -> bool {
+        if let DrillState::Running { position, velocity } = drill {
+            position.0 += velocity.0 * dt;
+            position.1 += velocity.1 * dt;
+            position.2 += velocity.2 * dt;
+            velocity.0 -= 10. * dt;
+            velocity.1 -= 10. * dt;
+            velocity.2 -= 10. * dt;
+            if position.0 > 1. {
+                position.0 -= 1.;
+            }
+            if position.1 > 1. {
+                position.1 -= 1.;
+            }
+            if position.2 > 1. {
+                position.2 -= 1.;
+            }
+            if velocity.0 < 0. {
+                velocity.0 = 0.
+            }
+            if velocity.1 < 0. {
+                velocity.1 = 0.
+            }
+            if velocity.2 < 0. {
+                velocity.2 = 0.
+            }
+            if velocity.0 == 0.
+                && velocity.1 == 0.
+                && velocity.2 == 0.
+                && position.0 > 0.25
+                && position.0 < 0.75
+                && position.1 > 0.25
+                && position.1 < 0.75
+                && position.2 > 0.25
+                && position.2 < 0.75
+            {
+                self.digging.drill();
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn grab_bucket(&mut self, bucket: &mut BucketState) {
+        if let BucketState::Empty | BucketState::Unheld(_) = bucket {
+            *bucket = BucketState::Held(0.);
+        }
+    }
+
+    pub fn release_bucket(&mut self, bucket: &mut BucketState) {
+        if let BucketState::Held(progress) = bucket {
+            *bucket = BucketState::Unheld(*progress);
+        }
+    }
+
+    pub fn hold_bucket(&mut self, bucket: &mut BucketState, dt: f32) -> Option<SimulationEvent> {
+        if let BucketState::Held(progress) = bucket {
+            *progress += dt;
+            if *progress > self.digging.balance.bucket_success_time {
+                let progress = *progress;
+                self.digging.empty_bucket();
+                if self.digging.no_buckets() {
+                    *bucket = BucketState::Finished(1.);
+                } else {
+                    *bucket = BucketState::Unheld(progress);
+                }
+                return Some(SimulationEvent::BucketEmptied);
+            }
+        }
+        None
+    }
+
+    pub fn buy(&mut self, upgrade: Upgrade) -> bool {
+        self.digging.buy(upgrade)
+    }
+
+    pub fn balance(&self) -> &Balance {
+        &self.digging.balance
+    }
+
+    /// Checks a typed answer against the right one under `validation`, sending the robot off if
+    /// it passes. Harder tiers send it off for longer, near misses for less.
+    pub fn solve_captcha(
+        &mut self,
+        robot: usize,
+        tier: usize,
+        answer: &str,
+        typed: &str,
+        validation: &CaptchaValidation,
+    ) -> CaptchaVerdict {
+        let verdict = validation.check(answer, typed);
+        let credit = validation.partial_credit;
+        self.digging.captcha_attempts.push(CaptchaAttempt {
+            time: self.digging.time_played,
+            typed: typed.to_string(),
+            answer: answer.to_string(),
+            verdict,
+        });
+        match verdict {
+            CaptchaVerdict::Correct => self.digging.solve_captcha(robot, tier, 1.),
+            CaptchaVerdict::Partial => self.digging.solve_captcha(robot, tier, credit),
+            CaptchaVerdict::Wrong => {}
+        }
+        verdict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::ToolId;
+
+    /// The default tuning without the pickaxe, so no rock ever gets in the way.
+    fn rockless() -> DiggingStatus {
+        let mut balance = Balance::default();
+        balance
+            .unlocks
+            .retain(|unlock| unlock.tool != ToolId::Pickaxe);
+        DiggingStatus::new(balance)
+    }
+
+    fn fill_buckets(digging: &mut DiggingStatus) {
+        while Simulation::new(digging).scoop() {}
+    }
+
+    #[test]
+    fn scoop_digs_a_block_into_the_buckets() {
+        let mut digging = rockless();
+        let depth = digging.depth;
+        assert!(Simulation::new(&mut digging).scoop());
+        assert_eq!(digging.depth, depth + digging.balance.scoops_per_block);
+        assert_eq!(digging.scoops_shoveled, 1);
+        assert_eq!(digging.scoops_in_top_bucket(), 1);
+    }
+
+    #[test]
+    fn scoop_stops_once_the_buckets_are_full() {
+        let mut digging = rockless();
+        let capacity = digging.balance.starting_buckets * digging.balance.scoops_per_bucket;
+        let mut simulation = Simulation::new(&mut digging);
+        for _ in 1..capacity {
+            assert!(simulation.scoop());
+        }
+        assert!(!simulation.scoop());
+        assert!(!digging.can_scoop());
+    }
+
+    #[test]
+    fn scoop_is_blocked_by_rock_until_the_pickaxe_breaks_it() {
+        let mut digging = DiggingStatus::default();
+        let rock = (0..10_000)
+            .find(|block| digging.is_rock(*block))
+            .expect("the default strata have rock");
+        let scoops_per_block = digging.balance.scoops_per_block;
+        digging.depth = rock * scoops_per_block;
+        let mut simulation = Simulation::new(&mut digging);
+        assert!(!simulation.scoop());
+        for _ in 1..simulation.balance().pickaxe_hits {
+            assert_eq!(simulation.swing_pickaxe(), Some(false));
+        }
+        assert_eq!(simulation.swing_pickaxe(), Some(true));
+        assert_eq!(digging.scoops_shoveled, 0);
+        assert_eq!(digging.depth, (rock + 1) * scoops_per_block);
+        assert_eq!(digging.rock_hits, 0);
+    }
+
+    #[test]
+    fn holding_a_bucket_empties_it() {
+        let mut digging = rockless();
+        fill_buckets(&mut digging);
+        let scoops_per_bucket = digging.scoops_per_bucket();
+        let hold = digging.balance.bucket_success_time;
+        let mut bucket = BucketState::Empty;
+        let mut simulation = Simulation::new(&mut digging);
+        simulation.grab_bucket(&mut bucket);
+        assert_eq!(simulation.hold_bucket(&mut bucket, hold / 2.), None);
+        assert_eq!(
+            simulation.hold_bucket(&mut bucket, hold),
+            Some(SimulationEvent::BucketEmptied)
+        );
+        assert!(matches!(bucket, BucketState::Unheld(_)));
+        assert_eq!(digging.buckets_emptied, 1);
+        assert_eq!(digging.dirt, scoops_per_bucket);
+    }
+
+    #[test]
+    fn emptying_the_last_bucket_finishes_it() {
+        let mut digging = rockless();
+        for _ in 0..digging.scoops_per_bucket() {
+            Simulation::new(&mut digging).scoop();
+        }
+        let hold = digging.balance.bucket_success_time;
+        let mut bucket = BucketState::Held(0.);
+        Simulation::new(&mut digging).hold_bucket(&mut bucket, hold * 2.);
+        assert!(matches!(bucket, BucketState::Finished(_)));
+        assert!(digging.no_buckets());
+    }
+
+    #[test]
+    fn drill_stops_when_its_time_runs_out() {
+        let mut digging = rockless();
+        digging.drill();
+        let depth = digging.depth;
+        let drill_time = digging.drill_time();
+        let mut stopped_at = None;
+        for step in 1..=(drill_time as usize + 5) * 10 {
+            let event = Simulation::new(&mut digging).advance_drill(0.1);
+            if event == Some(SimulationEvent::DrillStopped) {
+                stopped_at = Some(step as f32 * 0.1);
+                break;
+            }
+        }
+        let stopped_at = stopped_at.expect("the drill never stopped");
+        assert!((stopped_at - drill_time).abs() < 0.2);
+        assert_eq!(digging.drill_status, DrillStatus::Idling);
+        assert!(digging.depth > depth);
+    }
+
+    #[test]
+    fn robot_idles_when_its_time_runs_out() {
+        let mut digging = rockless();
+        digging.balance.robot_time = 10.;
+        fill_buckets(&mut digging);
+        digging.solve_captcha(0, 0, 1.);
+        let mut idle_at = None;
+        for step in 1..=200 {
+            let events = Simulation::new(&mut digging).advance_robot(0.1);
+            if events.contains(&SimulationEvent::RobotIdle(0)) {
+                idle_at = Some(step as f32 * 0.1);
+                break;
+            }
+        }
+        let idle_at = idle_at.expect("the robot never went idle");
+        assert!((idle_at - 10.).abs() < 0.2);
+        assert_eq!(digging.robots[0], RobotStatus::Idling);
+        // A quarter of a bucket a second, for ten seconds.
+        assert_eq!(digging.buckets_emptied, 2);
+    }
+
+    #[test]
+    fn progression_applies_every_unlock_passed_in_one_tick() {
+        let mut digging = DiggingStatus::default();
+        let balance = digging.balance.clone();
+        digging.depth = balance.scoops_per_block + 61 * balance.scoops_per_meter;
+        let events = Simulation::new(&mut digging).advance_progression();
+        let expected: Vec<_> = (0..balance.unlocks.len())
+            .filter(|index| (1..=61).contains(&balance.unlocks[*index].meter))
+            .map(SimulationEvent::Unlocked)
+            .collect();
+        assert_eq!(expected.len(), 4);
+        assert_eq!(events, expected);
+        assert_eq!(digging.progression(), 61);
+        assert_eq!(digging.meter_times.len(), 61);
+        assert_eq!(digging.drill_status, DrillStatus::Idling);
+        assert_eq!(digging.robots[0], RobotStatus::Idling);
+        assert_eq!(digging.robots[1], RobotStatus::Idling);
+        assert_eq!(digging.robots[2], RobotStatus::Locked);
+        assert_eq!(
+            Simulation::new(&mut digging).advance_progression(),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn catch_up_runs_the_drill_only_as_long_as_it_had_left() {
+        let mut digging = rockless();
+        digging.drill();
+        let drill_time = digging.drill_time();
+        let expected = digging.drill_speed() * drill_time;
+        let away = Simulation::new(&mut digging).catch_up(3600.);
+        assert_eq!(away.elapsed, 3600.);
+        assert!((away.scoops as f32 - expected).abs() <= 2.);
+        assert_eq!(away.buckets, 0);
+        match digging.drill_status {
+            DrillStatus::Running { time_left, .. } => assert!(time_left <= 0.),
+            status => assert_eq!(status, DrillStatus::Idling),
+        }
+    }
+
+    #[test]
+    fn catch_up_leaves_the_rest_of_a_timer_running() {
+        let mut digging = rockless();
+        fill_buckets(&mut digging);
+        digging.solve_captcha(0, 0, 1.);
+        let robot_time = digging.robot_time(0);
+        // Long enough for one bucket, at a quarter of a bucket a second.
+        let away = Simulation::new(&mut digging).catch_up(6.);
+        assert_eq!(away.scoops, 0);
+        assert_eq!(away.buckets, 1);
+        match digging.robots[0] {
+            RobotStatus::Running { time_left, .. } => {
+                assert!((time_left - (robot_time - 6.)).abs() < 0.01)
+            }
+            status => panic!("the robot should still be running, not {:?}", status),
+        }
+    }
+}
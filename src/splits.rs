@@ -0,0 +1,287 @@
+use crate::balance::Balance;
+use crate::cards::{AlertState, ToolId};
+use crate::config::{load_user_file, store_user_file, user_file};
+use crate::leaderboard::format_time;
+use crate::modes::GameMode;
+use crate::prelude::*;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const AHEAD: [f32; 4] = [0.4, 1., 0.4, 1.];
+const BEHIND: [f32; 4] = [1., 0.4, 0.4, 1.];
+const EVEN: [f32; 4] = [1., 1., 1., 1.];
+
+/// A meter the splits file marks, named for the tools it unlocks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitPoint {
+    pub meter: u32,
+    pub name: String,
+}
+
+/// Every tenth meter, each meter a drill or robot unlocks at, and the finish.
+pub fn split_points(balance: &Balance) -> Vec<SplitPoint> {
+    let mut points: Vec<SplitPoint> = (1..=balance.victory_depth)
+        .filter(|meter| meter % 10 == 0 || *meter == balance.victory_depth)
+        .map(|meter| SplitPoint {
+            meter,
+            name: format!("{}m", meter),
+        })
+        .collect();
+    for unlock in balance.unlocks.iter() {
+        let tool = match (unlock.tool, unlock.state) {
+            (ToolId::Drill, _) => "Drill".to_string(),
+            (ToolId::Robot, AlertState::Robot(robot, _)) => format!("Robot {}", robot + 1),
+            _ => continue,
+        };
+        if unlock.meter == 0 {
+            continue;
+        }
+        match points.iter_mut().find(|point| point.meter == unlock.meter) {
+            Some(point) => point.name = format!("{} - {}", point.name, tool),
+            None => points.push(SplitPoint {
+                meter: unlock.meter,
+                name: format!("{}m - {}", unlock.meter, tool),
+            }),
+        }
+    }
+    points.sort_by_key(|point| point.meter);
+    points
+}
+
+/// When a finished run reached each meter, up to and including the finish.
+pub fn run_splits(digging: &DiggingStatus) -> Vec<f32> {
+    // The run ends as the last meter is dug, before progression gets to count it.
+    let finish = digging.balance.victory_depth.max(1) as usize;
+    let mut splits: Vec<f32> = digging
+        .meter_times
+        .iter()
+        .take(finish - 1)
+        .copied()
+        .collect();
+    splits.resize(finish, digging.time_played);
+    splits
+}
+
+/// The fastest run's splits and the best time seen for each stretch between split points, for
+/// one game mode. Kept in `splits-<mode>.ron` and exported to `splits-<mode>.lss` for speedrun
+/// timers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Splits {
+    pub mode: GameMode,
+    pub personal_best: Vec<f32>, // Seconds into the run each meter was reached, meter 1 first.
+    pub best_segments: Vec<(u32, u32, f32)>, // From meter, to meter, fastest seconds between.
+    pub attempts: u32,
+}
+
+pub fn splits_path(mode: &GameMode) -> PathBuf {
+    user_file(&format!("splits-{}.ron", mode.slug()))
+}
+
+pub fn livesplit_path(mode: &GameMode) -> PathBuf {
+    splits_path(mode).with_extension("lss")
+}
+
+impl Splits {
+    pub fn load(mode: &GameMode) -> Splits {
+        load_user_file(&splits_path(mode), "splits").unwrap_or_else(|| Splits {
+            mode: mode.clone(),
+            ..Splits::default()
+        })
+    }
+
+    pub fn store(&self) {
+        store_user_file(self, &splits_path(&self.mode), "splits");
+    }
+
+    /// The personal best's time at `meter`, if it got there.
+    pub fn best_at(&self, meter: u32) -> Option<f32> {
+        meter
+            .checked_sub(1)
+            .and_then(|index| self.personal_best.get(index as usize))
+            .copied()
+    }
+
+    pub fn best_segment(&self, from: u32, to: u32) -> Option<f32> {
+        self.best_segments
+            .iter()
+            .find(|(start, end, _)| *start == from && *end == to)
+            .map(|(_, _, time)| *time)
+    }
+
+    /// Adds a finished run, taking it as the personal best if it was faster.
+    pub fn record(&mut self, digging: &DiggingStatus) {
+        self.attempts += 1;
+        let splits = run_splits(digging);
+        let at = |meter: u32| {
+            if meter == 0 {
+                0.
+            } else {
+                splits[meter as usize - 1]
+            }
+        };
+        let mut from = 0;
+        for point in split_points(&digging.balance) {
+            let time = at(point.meter) - at(from);
+            match self
+                .best_segments
+                .iter_mut()
+                .find(|(start, end, _)| *start == from && *end == point.meter)
+            {
+                Some(best) if time < best.2 => best.2 = time,
+                Some(_) => {}
+                None => self.best_segments.push((from, point.meter, time)),
+            }
+            from = point.meter;
+        }
+        let faster = match self.personal_best.last() {
+            // A balance change that moved the finish makes the old best incomparable.
+            Some(best) => splits.len() != self.personal_best.len() || digging.time_played < *best,
+            None => true,
+        };
+        if faster {
+            self.personal_best = splits;
+        }
+    }
+
+    /// Writes the splits as a LiveSplit `.lss` file, which most speedrun timers can import.
+    pub fn export(&self, balance: &Balance) {
+        let path = livesplit_path(&self.mode);
+        let mut segments = String::new();
+        let mut from = 0;
+        for point in split_points(balance) {
+            segments.push_str("    <Segment>\n");
+            segments.push_str(&format!(
+                "      <Name>{}</Name>\n      <Icon />\n",
+                point.name
+            ));
+            segments.push_str("      <SplitTimes>\n");
+            match self.best_at(point.meter) {
+                Some(time) => segments.push_str(&format!(
+                    "        <SplitTime name=\"Personal Best\">\n          <RealTime>{}</RealTime>\n          <GameTime>{}</GameTime>\n        </SplitTime>\n",
+                    lss_time(time),
+                    lss_time(time)
+                )),
+                None => segments.push_str("        <SplitTime name=\"Personal Best\" />\n"),
+            }
+            segments.push_str("      </SplitTimes>\n");
+            match self.best_segment(from, point.meter) {
+                Some(time) => segments.push_str(&format!(
+                    "      <BestSegmentTime>\n        <RealTime>{}</RealTime>\n        <GameTime>{}</GameTime>\n      </BestSegmentTime>\n",
+                    lss_time(time),
+                    lss_time(time)
+                )),
+                None => segments.push_str("      <BestSegmentTime />\n"),
+            }
+            segments.push_str("      <SegmentHistory />\n    </Segment>\n");
+            from = point.meter;
+        }
+        let lss = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Run version=\"1.7.0\">\n  <GameIcon />\n  <GameName>Digging Deeper</GameName>\n  <CategoryName>{}m {}</CategoryName>\n  <Offset>00:00:00</Offset>\n  <AttemptCount>{}</AttemptCount>\n  <AttemptHistory />\n  <Segments>\n{}  </Segments>\n  <AutoSplitterSettings />\n</Run>\n",
+            balance.victory_depth,
+            self.mode.name(),
+            self.attempts,
+            segments
+        );
+        if let Err(err) = std::fs::write(&path, lss) {
+            warn!("Could not write {:?}: {}", path, err);
+        }
+    }
+}
+
+/// LiveSplit's `hh:mm:ss.fffffff`.
+fn lss_time(seconds: f32) -> String {
+    let whole = seconds.max(0.) as u32;
+    let ticks = ((seconds.max(0.) - whole as f32) * 10_000_000.) as u32;
+    format!(
+        "{:02}:{:02}:{:02}.{:07}",
+        whole / 3600,
+        whole / 60 % 60,
+        whole % 60,
+        ticks
+    )
+}
+
+/// `+1.2` when behind the personal best, `-1.2` when ahead.
+fn format_delta(delta: f32) -> String {
+    if delta.abs() >= 60. {
+        let sign = if delta < 0. { "-" } else { "+" };
+        format!("{}{}", sign, format_time(delta.abs()))
+    } else {
+        format!("{:+.1}", delta)
+    }
+}
+
+fn delta_color(delta: f32) -> [f32; 4] {
+    if delta < 0. {
+        AHEAD
+    } else if delta > 0. {
+        BEHIND
+    } else {
+        EVEN
+    }
+}
+
+/// Fills in the split timer: the run's time, the last meter against the personal best, and the
+/// last tool unlock against it too.
+pub struct SplitTimerSystem;
+
+impl<'s> System<'s> for SplitTimerSystem {
+    type SystemData = (
+        Read<'s, DiggingStatus>,
+        Read<'s, Splits>,
+        WriteStorage<'s, UiText>,
+        UiFinder<'s>,
+    );
+
+    fn run(&mut self, (digging, splits, mut texts, finder): Self::SystemData) {
+        if let Some(text) = finder.find("split_time").and_then(|ent| texts.get_mut(ent)) {
+            text.text = format_time(digging.time_played);
+        }
+        let meter = digging.meter_times.len() as u32;
+        if let Some(text) = finder
+            .find("split_meter")
+            .and_then(|ent| texts.get_mut(ent))
+        {
+            match (digging.meter_times.last(), splits.best_at(meter)) {
+                (Some(time), Some(best)) => {
+                    let delta = time - best;
+                    text.text = format!("{}m: {}", meter, format_delta(delta));
+                    text.color = delta_color(delta);
+                }
+                _ => {
+                    text.text = String::new();
+                }
+            }
+        }
+        if let Some(text) = finder
+            .find("split_milestone")
+            .and_then(|ent| texts.get_mut(ent))
+        {
+            let milestone = split_points(&digging.balance)
+                .into_iter()
+                .filter(|point| point.meter <= meter && point.name.contains(" - "))
+                .last();
+            match milestone {
+                Some(point) => {
+                    let time = digging.meter_times[point.meter as usize - 1];
+                    match splits.best_at(point.meter) {
+                        Some(best) => {
+                            let delta = time - best;
+                            text.text = format!("{}: {}", point.name, format_delta(delta));
+                            text.color = delta_color(delta);
+                        }
+                        None => {
+                            text.text = format!("{}: {}", point.name, format_time(time));
+                            text.color = EVEN;
+                        }
+                    }
+                }
+                None => {
+                    text.text = String::new();
+                }
+            }
+        }
+    }
+}
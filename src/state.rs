@@ -1,8 +1,17 @@
+use crate::achievements::Achievements;
 use crate::assets::*;
-use crate::captcha;
+use crate::balance::Balance;
+use crate::daily::{DailyChallenge, DailyHistory};
+use crate::digging::{spawn_robot, spawn_unlock_alertable};
 use crate::hole::spawn_hole;
-use crate::hole::VICTORY_DEPTH;
+use crate::leaderboard::{format_date, format_time, Leaderboard, NewBests, RunRecord};
+use crate::modes::{Difficulty, GameMode, Modifier};
 use crate::prelude::*;
+use crate::profile::{Bonus, Profile};
+use crate::replay::{replay_directory, ReplayRecorder};
+use crate::save::{now, Autosave, SaveGame};
+use crate::simulation::{AwaySummary, Simulation};
+use crate::splits::Splits;
 use amethyst::{
     assets::{AssetStorage, Loader},
     audio::output::init_output,
@@ -34,6 +43,11 @@ fn init_camera(world: &mut World, dimensions: &ScreenDimensions) {
 
 struct GameplayState {
     assets: GameAssets,
+    save: Option<SaveGame>,        // The run to pick back up, if continuing.
+    endless: bool,                 // Whether a new run keeps going past victory_depth.
+    mode: GameMode,                // What a new run is played in.
+    daily: Option<DailyChallenge>, // Set when a new run is the daily challenge instead.
+    away_card: Option<Entity>,
 }
 
 impl SimpleState for GameplayState {
@@ -41,18 +55,76 @@ impl SimpleState for GameplayState {
         data.world.delete_all();
         data.world.insert(self.assets.0.clone());
         data.world.insert(self.assets.1.clone());
-        data.world.insert(self.assets.2.clone());
+        let save = self.save.take();
+        let away = data.world.exec(
+            |(mut digging, mut rng, mut recorder, mut autosave, mut splits, profile, base): (
+                Write<'_, DiggingStatus>,
+                Write<'_, GameRng>,
+                Write<'_, ReplayRecorder>,
+                Write<'_, Autosave>,
+                Write<'_, Splits>,
+                Read<'_, Profile>,
+                Read<'_, Balance>,
+            )| {
+                let away = match save {
+                    Some(save) => {
+                        let (mode, balance) = save.digging.mode.fitted(&base);
+                        *digging = DiggingStatus {
+                            mode,
+                            ..save.digging.with_balance(balance)
+                        };
+                        rng.reseed(save.seed);
+                        let elapsed = now().saturating_sub(save.saved_at) as f32;
+                        Some(Simulation::new(&mut digging).catch_up(elapsed))
+                    }
+                    None => match &self.daily {
+                        // Everyone gets the same run, so no seed of our own and no bonuses.
+                        Some(daily) => {
+                            *digging = DiggingStatus::with_mode(&base, daily.mode.clone());
+                            digging.daily = Some(daily.day);
+                            rng.reseed(daily.seed);
+                            digging.rock_seed = rng.seed();
+                            None
+                        }
+                        None => {
+                            *digging = DiggingStatus::with_mode(&base, self.mode.clone());
+                            rng.next_run();
+                            digging.rock_seed = rng.seed();
+                            digging.endless = self.endless;
+                            digging.apply_bonuses(profile.bonuses);
+                            None
+                        }
+                    },
+                };
+                let unlocked: Vec<_> = digging
+                    .balance
+                    .unlocks
+                    .iter()
+                    .filter(|unlock| digging.is_unlocked(unlock))
+                    .cloned()
+                    .collect();
+                for unlock in unlocked.iter() {
+                    digging.unlock(unlock);
+                }
+                *splits = Splits::load(&digging.mode);
+                recorder.start(rng.seed(), &digging);
+                autosave.enabled = true;
+                away
+            },
+        );
         let dimensions = (*data.world.read_resource::<ScreenDimensions>()).clone();
         init_camera(data.world, &dimensions);
         spawn_hole(data.world);
         data.world.exec(
-            |(mut spawner, mut alertables, mut buckets, mut robots): (
+            |(digging, mut spawner, mut alertables, mut buckets, mut robots): (
+                Read<'_, DiggingStatus>,
                 WidgetSpawner,
                 WriteStorage<'_, crate::cards::Alertable>,
                 WriteStorage<'_, crate::digging::Bucket>,
                 WriteStorage<'_, crate::digging::Robot>,
             )| {
                 spawner.spawn_ui_widget("prefabs/depth.ron", Position { x: 0., y: -16. });
+                spawner.spawn_ui_widget("prefabs/splits.ron", Position { x: -16., y: -16. });
                 for i in 0..16 {
                     let bucket_entity = spawner.spawn_ui_widget(
                         "prefabs/bucket.ron",
@@ -65,41 +137,64 @@ impl SimpleState for GameplayState {
                         .insert(bucket_entity, crate::digging::Bucket { index: i })
                         .expect("Unreachable, entity just created");
                 }
-                let robot_entity =
-                    spawner.spawn_ui_widget("prefabs/robot.ron", Position { x: -112., y: 48. });
-                robots
-                    .insert(robot_entity, crate::digging::Robot { index: 0 })
-                    .expect("Unreachable, entity just created");
-                let alert_entity = spawner.spawn_ui_widget(
-                    "prefabs/shovel_alertable.ron",
-                    Position { x: -64., y: -32. },
-                );
-                alertables
-                    .insert(
-                        alert_entity,
-                        crate::cards::Alertable {
-                            state: crate::cards::AlertState::Shovel(
-                                crate::cards::ShovelAlertState::Ready,
-                            ),
-                            clicked: false,
-                        },
-                    )
-                    .expect("Unreachable: entity just created");
-                let bucket_alert_entity = spawner.spawn_ui_widget(
-                    "prefabs/bucket_alertable.ron",
-                    Position { x: -64., y: -96. },
-                );
-                alertables
-                    .insert(
-                        bucket_alert_entity,
-                        crate::cards::Alertable {
-                            state: crate::cards::AlertState::Bucket(
-                                crate::cards::BucketAlertState::Empty,
-                            ),
-                            clicked: false,
-                        },
-                    )
-                    .expect("Unreachable: entity just created");
+                for index in 0..digging.robots.len() {
+                    spawn_robot(&mut spawner, &mut robots, index);
+                }
+                // Progression only announces unlocks as they're passed, so spawn the ones behind us.
+                for unlock in digging.balance.unlocks.iter() {
+                    if digging.is_unlocked(unlock) {
+                        spawn_unlock_alertable(&mut spawner, &mut alertables, unlock);
+                    }
+                }
+            },
+        );
+        if let Some(away) = away.filter(|away| away.scoops > 0 || away.buckets > 0) {
+            data.world.insert(away);
+            self.away_card = Some(data.world.exec(|mut spawner: WidgetSpawner| {
+                spawner.spawn_ui_widget("prefabs/away_card.ron", Position { x: 0., y: 224. })
+            }));
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Ui(ui_event) = event {
+            if ui_event.event_type == UiEventType::Click {
+                data.world
+                    .exec(|(finder, entities): (UiFinder<'_>, Entities<'_>)| {
+                        if let Some(dismiss) = finder.find("dismiss_away") {
+                            if dismiss == ui_event.target {
+                                if let Some(away_card) = self.away_card.take() {
+                                    entities
+                                        .delete(away_card)
+                                        .expect("Unreachable, entity definitely exists");
+                                }
+                            }
+                        }
+                    });
+            }
+        }
+        Trans::None
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world.exec(
+            |(digging, rng, mut recorder, mut autosave): (
+                Read<'_, DiggingStatus>,
+                Read<'_, GameRng>,
+                Write<'_, ReplayRecorder>,
+                Write<'_, Autosave>,
+            )| {
+                recorder.finish(&digging, &replay_directory());
+                autosave.enabled = false;
+                if digging.reached_victory() {
+                    SaveGame::delete();
+                } else {
+                    SaveGame::new(&digging, rng.seed()).store();
+                }
             },
         );
     }
@@ -107,7 +202,7 @@ impl SimpleState for GameplayState {
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
         data.world
             .exec(|(digging,): (Read<'_, crate::digging::DiggingStatus>,)| {
-                if digging.depth > VICTORY_DEPTH * SCOOPS_PER_METER {
+                if digging.reached_victory() {
                     return Trans::Switch(Box::new(GameOverState {
                         assets: self.assets.clone(),
                     }));
@@ -202,7 +297,16 @@ impl SimpleState for LoadingState {
             "audio/robot_unlock.wav".to_string(),
             &mut progress_counter,
         );
-        let captchas = captcha::get_captchas(data.world, &mut progress_counter);
+        let pickaxe_hit = load_sound_file(
+            data.world,
+            "audio/pickaxe_hit.wav".to_string(),
+            &mut progress_counter,
+        );
+        let pickaxe_break = load_sound_file(
+            data.world,
+            "audio/pickaxe_break.wav".to_string(),
+            &mut progress_counter,
+        );
         self.progress = Some(progress_counter);
         self.assets = Some((
             SpriteStorage {
@@ -221,8 +325,9 @@ impl SimpleState for LoadingState {
                 robot_captcha_fail,
                 robot_captcha_key,
                 robot_unlock,
+                pickaxe_hit,
+                pickaxe_break,
             },
-            captchas,
         ));
 
         init_output(data.world);
@@ -237,6 +342,7 @@ impl SimpleState for LoadingState {
             if progress.is_complete() {
                 return SimpleTrans::Switch(Box::new(TitleViewState {
                     assets: self.assets.clone().unwrap(),
+                    continue_checked: false,
                 }));
             }
         }
@@ -246,6 +352,7 @@ impl SimpleState for LoadingState {
 
 struct TitleViewState {
     assets: GameAssets,
+    continue_checked: bool, // Whether we've hidden Continue yet, when there's nothing to continue.
 }
 
 impl SimpleState for TitleViewState {
@@ -255,6 +362,24 @@ impl SimpleState for TitleViewState {
         });
     }
 
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if !self.continue_checked {
+            data.world.exec(
+                |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
+                    if let Some(resume) = finder.find("continue") {
+                        self.continue_checked = true;
+                        if !SaveGame::exists() {
+                            hidden
+                                .insert(resume, HiddenPropagate::new())
+                                .expect("Unreachable, entity exists");
+                        }
+                    }
+                },
+            );
+        }
+        Trans::None
+    }
+
     fn handle_event(
         &mut self,
         data: StateData<'_, GameData<'_, '_>>,
@@ -265,8 +390,53 @@ impl SimpleState for TitleViewState {
                 if ui_event.event_type == UiEventType::Click {
                     if let Some(play) = finder.find("play") {
                         if play == ui_event.target {
+                            return SimpleTrans::Switch(Box::new(ModeSelectState {
+                                assets: self.assets.clone(),
+                                endless: false,
+                                mode: GameMode::default(),
+                            }));
+                        }
+                    }
+                    if let Some(endless) = finder.find("endless") {
+                        if endless == ui_event.target {
+                            return SimpleTrans::Switch(Box::new(ModeSelectState {
+                                assets: self.assets.clone(),
+                                endless: true,
+                                mode: GameMode::default(),
+                            }));
+                        }
+                    }
+                    if let Some(daily) = finder.find("daily") {
+                        if daily == ui_event.target {
                             return SimpleTrans::Switch(Box::new(GameplayState {
                                 assets: self.assets.clone(),
+                                save: None,
+                                endless: false,
+                                mode: GameMode::default(), // The daily challenge has its own.
+                                daily: Some(DailyChallenge::today()),
+                                away_card: None,
+                            }));
+                        }
+                    }
+                    if let Some(resume) = finder.find("continue") {
+                        if resume == ui_event.target {
+                            if let Some(save) = SaveGame::load() {
+                                return SimpleTrans::Switch(Box::new(GameplayState {
+                                    assets: self.assets.clone(),
+                                    save: Some(save),
+                                    endless: false,
+                                    mode: GameMode::default(), // The save has its own.
+                                    daily: None,
+                                    away_card: None,
+                                }));
+                            }
+                        }
+                    }
+                    if let Some(achievements) = finder.find("achievements") {
+                        if achievements == ui_event.target {
+                            return SimpleTrans::Switch(Box::new(AchievementsState {
+                                assets: self.assets.clone(),
+                                listed: false,
                             }));
                         }
                     }
@@ -282,33 +452,320 @@ impl SimpleState for TitleViewState {
         }
     }
 }
+
+/// Picks the difficulty and modifiers for a new run, between the title view and the game.
+struct ModeSelectState {
+    assets: GameAssets,
+    endless: bool,
+    mode: GameMode,
+}
+
+impl SimpleState for ModeSelectState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world.delete_all();
+        data.world.exec(|mut spawner: WidgetSpawner| {
+            spawner.spawn_ui_widget("prefabs/mode_select.ron", Position { x: 0., y: 0. })
+        });
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        data.world.exec(
+            |(finder, mut texts): (UiFinder<'_>, WriteStorage<'_, UiText>)| {
+                if let Some(text) = finder
+                    .find("difficulty_state")
+                    .and_then(|ent| texts.get_mut(ent))
+                {
+                    text.text = format!("Difficulty: {}", self.mode.difficulty.name());
+                }
+                for modifier in Modifier::ALL.iter() {
+                    if let Some(text) = finder
+                        .find(modifier.label())
+                        .and_then(|ent| texts.get_mut(ent))
+                    {
+                        let state = if self.mode.has(*modifier) {
+                            "On"
+                        } else {
+                            "Off"
+                        };
+                        text.text = format!("{}: {}", modifier.name(), state);
+                    }
+                }
+            },
+        );
+        Trans::None
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match event {
+            StateEvent::Ui(ui_event) if ui_event.event_type == UiEventType::Click => {
+                let name = data.world.exec(|transforms: ReadStorage<'_, UiTransform>| {
+                    get_ui_name(ui_event.target, &transforms)
+                });
+                if let Some(difficulty) = Difficulty::from_button(&name) {
+                    self.mode.difficulty = difficulty;
+                } else if let Some(modifier) = Modifier::from_button(&name) {
+                    self.mode.toggle(modifier);
+                } else if name == "start" {
+                    return SimpleTrans::Switch(Box::new(GameplayState {
+                        assets: self.assets.clone(),
+                        save: None,
+                        endless: self.endless,
+                        mode: self.mode.clone(),
+                        daily: None,
+                        away_card: None,
+                    }));
+                } else if name == "back" {
+                    return SimpleTrans::Switch(Box::new(TitleViewState {
+                        assets: self.assets.clone(),
+                        continue_checked: false,
+                    }));
+                }
+                Trans::None
+            }
+            _ => Trans::None,
+        }
+    }
+}
+
+/// Lists every achievement, earned or not, from the title view.
+struct AchievementsState {
+    assets: GameAssets,
+    listed: bool, // Whether the list has been filled in yet.
+}
+
+impl SimpleState for AchievementsState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world.delete_all();
+        data.world.exec(|mut spawner: WidgetSpawner| {
+            spawner.spawn_ui_widget("prefabs/achievements_view.ron", Position { x: 0., y: 0. })
+        });
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world.delete_all();
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if !self.listed {
+            data.world.exec(
+                |(finder, mut texts, achievements, profile): (
+                    UiFinder<'_>,
+                    WriteStorage<'_, UiText>,
+                    Read<'_, Achievements>,
+                    Read<'_, Profile>,
+                )| {
+                    let (list, count) = match (
+                        finder.find("achievements_list"),
+                        finder.find("achievements_count"),
+                    ) {
+                        (Some(list), Some(count)) => (list, count),
+                        _ => return,
+                    };
+                    let mut earned = 0;
+                    let mut lines = Vec::new();
+                    for achievement in achievements.list.iter() {
+                        let done = profile.achievements.contains(&achievement.id);
+                        if done {
+                            earned += 1;
+                        }
+                        lines.push(format!(
+                            "[{}] {} - {}",
+                            if done { "x" } else { " " },
+                            achievement.name,
+                            achievement.description
+                        ));
+                    }
+                    if let Some(text) = texts.get_mut(list) {
+                        text.text = lines.join("\n");
+                    }
+                    if let Some(text) = texts.get_mut(count) {
+                        text.text =
+                            format!("Achievements ({}/{})", earned, achievements.list.len());
+                    }
+                    self.listed = true;
+                },
+            );
+        }
+        Trans::None
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match event {
+            StateEvent::Ui(ui_event) => data.world.exec(|finder: UiFinder<'_>| {
+                if ui_event.event_type == UiEventType::Click {
+                    if let Some(back) = finder.find("back") {
+                        if back == ui_event.target {
+                            return SimpleTrans::Switch(Box::new(TitleViewState {
+                                assets: self.assets.clone(),
+                                continue_checked: false,
+                            }));
+                        }
+                    }
+                }
+                Trans::None
+            }),
+            _ => Trans::None,
+        }
+    }
+}
+
+/// Text color for records the run just set, on the game over screen.
+fn highlight(new_best: bool) -> [f32; 4] {
+    if new_best {
+        [1., 0.85, 0.3, 1.]
+    } else {
+        [1., 1., 1., 1.]
+    }
+}
+
 pub struct EndGameRenderer;
 
 impl<'s> System<'s> for EndGameRenderer {
     // Also needed: Components for UI, not sure what we'll use yet.
     type SystemData = (
         Read<'s, DiggingStatus>,
+        Read<'s, GameRng>,
+        Read<'s, Profile>,
+        Read<'s, Leaderboard>,
+        Read<'s, DailyHistory>,
         WriteStorage<'s, UiText>,
         ReadStorage<'s, UiTransform>,
     );
 
-    fn run(&mut self, (digging, mut texts, transforms): Self::SystemData) {
+    fn run(
+        &mut self,
+        (digging, rng, profile, leaderboard, daily_history, mut texts, transforms): Self::SystemData,
+    ) {
+        let best = &leaderboard.last;
         for (transform, mut text) in (&transforms, &mut texts).join() {
+            if let Some(row) = transform
+                .id
+                .strip_prefix("leaderboard_")
+                .and_then(|row| row.parse::<usize>().ok())
+            {
+                text.text = match digging.daily {
+                    // Daily runs stay off the leaderboard, so the day's own best stands in.
+                    Some(day) if row == 0 => match daily_history.result(day) {
+                        Some(result) => format!(
+                            "Best: {} ({} attempts)",
+                            format_time(result.time_played),
+                            result.attempts
+                        ),
+                        None => String::new(),
+                    },
+                    Some(_) => String::new(),
+                    None => match leaderboard.fastest_in(&digging.mode).nth(row) {
+                        Some(run) => format!(
+                            "{}. {} - {}",
+                            row + 1,
+                            format_time(run.time_played),
+                            format_date(run.finished_at)
+                        ),
+                        None => format!("{}. ---", row + 1),
+                    },
+                };
+                text.color = highlight(best.rank == Some(row));
+                continue;
+            }
+            if let Some(bonus) = Bonus::ALL
+                .iter()
+                .find(|bonus| bonus.label() == transform.id)
+            {
+                let level = profile.bonuses.level(*bonus);
+                text.text = match profile.cost(*bonus, &digging.balance) {
+                    Some(cost) => format!("{} ({}): {}", bonus.name(), level, cost),
+                    None => format!("{} ({}): Maxed", bonus.name(), level),
+                };
+                continue;
+            }
             match transform.id.as_ref() {
                 "scoops_shoveled" => {
                     text.text = format!("Scoops Shoveled: {}", digging.scoops_shoveled);
                 }
                 "drill_pulls" => {
                     text.text = format!("Pull Cords Pulled: {}", digging.drill_pulls);
+                    if best.fewest_drill_pulls {
+                        text.text.push_str(" - Record!");
+                    }
+                    text.color = highlight(best.fewest_drill_pulls);
                 }
                 "drills_started" => {
                     text.text = format!("Drills Started: {}", digging.drills_started);
                 }
                 "captchas_solved" => {
-                    text.text = format!("Captchas Solved: {}", digging.captchas_solved);
+                    // Passed out of answered, over every kind of puzzle robots ask for.
+                    text.text = format!(
+                        "Robot Challenges: {}/{}",
+                        digging.captchas_solved,
+                        digging.captcha_attempts.len()
+                    );
+                    if best.most_captchas {
+                        text.text.push_str(" - Record!");
+                    }
+                    text.color = highlight(best.most_captchas);
+                }
+                "daily_streak" => {
+                    text.text = match digging.daily {
+                        Some(day) => format!(
+                            "Daily Challenge {}: {} day streak",
+                            DailyChallenge::for_day(day).date(),
+                            daily_history.streak(day)
+                        ),
+                        None => String::new(),
+                    };
+                }
+                "leaderboard_title" => {
+                    text.text = match digging.daily {
+                        Some(_) => "Today's Best".to_string(),
+                        None => format!("Fastest Runs ({})", digging.mode.name()),
+                    };
                 }
                 "time_played" => {
-                    text.text = format!("Time Played: {}", digging.time_played);
+                    text.text = format!("Time Played: {}", format_time(digging.time_played));
+                    if best.fastest {
+                        text.text.push_str(" - New Personal Best!");
+                    }
+                    text.color = highlight(best.fastest);
+                }
+                "record_drill_pulls" | "record_captchas" if digging.daily.is_some() => {
+                    text.text = String::new();
+                }
+                "record_drill_pulls" => {
+                    text.text = match leaderboard.fewest_drill_pulls_in(&digging.mode) {
+                        Some(run) => format!(
+                            "Fewest Pulls: {} ({})",
+                            run.drill_pulls,
+                            format_date(run.finished_at)
+                        ),
+                        None => "Fewest Pulls: ---".to_string(),
+                    };
+                }
+                "record_captchas" => {
+                    text.text = match leaderboard.most_captchas_in(&digging.mode) {
+                        Some(run) => format!(
+                            "Most Challenges: {} ({})",
+                            run.captchas_solved,
+                            format_date(run.finished_at)
+                        ),
+                        None => "Most Challenges: ---".to_string(),
+                    };
+                }
+                "seed" => {
+                    text.text = format!("Seed: {}", rng.seed());
+                }
+                "prestige" => {
+                    text.text = format!(
+                        "Prestige: {} (+{} this run)",
+                        profile.prestige, profile.last_earned
+                    );
                 }
                 _ => {}
             }
@@ -316,6 +773,40 @@ impl<'s> System<'s> for EndGameRenderer {
     }
 }
 
+pub struct AwaySummaryRenderer;
+
+impl<'s> System<'s> for AwaySummaryRenderer {
+    type SystemData = (
+        Option<Read<'s, AwaySummary>>,
+        WriteStorage<'s, UiText>,
+        ReadStorage<'s, UiTransform>,
+    );
+
+    fn run(&mut self, (away, mut texts, transforms): Self::SystemData) {
+        if let Some(away) = away {
+            for (transform, mut text) in (&transforms, &mut texts).join() {
+                match transform.id.as_ref() {
+                    "away_time" => {
+                        let minutes = away.elapsed as u32 / 60;
+                        text.text = format!(
+                            "While you were away ({}m {}s)",
+                            minutes,
+                            away.elapsed as u32 % 60
+                        );
+                    }
+                    "away_scoops" => {
+                        text.text = format!("Scoops Dug: {}", away.scoops);
+                    }
+                    "away_buckets" => {
+                        text.text = format!("Buckets Hauled: {}", away.buckets);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
 struct GameOverState {
     assets: GameAssets,
 }
@@ -324,8 +815,40 @@ impl SimpleState for GameOverState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         data.world.delete_all();
         data.world.exec(
-            |(mut spawner, mut digging): (WidgetSpawner, Write<'_, DiggingStatus>)| {
+            |(
+                mut spawner,
+                mut digging,
+                mut profile,
+                mut leaderboard,
+                mut splits,
+                mut daily_history,
+                rng,
+            ): (
+                WidgetSpawner,
+                Write<'_, DiggingStatus>,
+                Write<'_, Profile>,
+                Write<'_, Leaderboard>,
+                Write<'_, Splits>,
+                Write<'_, DailyHistory>,
+                Read<'_, GameRng>,
+            )| {
                 digging.game_over = true;
+                if let Some(day) = digging.daily {
+                    // No prestige either, or replaying the day's seed would farm it.
+                    profile.last_earned = 0;
+                    // The highlights are still the last normal run's, so clear them.
+                    leaderboard.last = NewBests::default();
+                    daily_history.record(day, digging.time_played);
+                    daily_history.store();
+                } else {
+                    profile.award(&digging);
+                    leaderboard.record(RunRecord::new(&digging, rng.seed()));
+                    leaderboard.store();
+                    splits.record(&digging);
+                    splits.store();
+                    splits.export(&digging.balance);
+                }
+                profile.store();
                 spawner.spawn_ui_widget("prefabs/game_over.ron", Position { x: 0., y: 0. })
             },
         );
@@ -338,13 +861,34 @@ impl SimpleState for GameOverState {
     ) -> SimpleTrans {
         match event {
             StateEvent::Ui(ui_event) => data.world.exec(
-                |(finder, mut digging): (UiFinder<'_>, Write<'_, DiggingStatus>)| {
+                |(finder, mut digging, mut profile, ui_transforms, sounds): (
+                    UiFinder<'_>,
+                    Write<'_, DiggingStatus>,
+                    Write<'_, Profile>,
+                    ReadStorage<'_, UiTransform>,
+                    SoundPlayer<'_>,
+                )| {
                     if ui_event.event_type == UiEventType::Click {
+                        if let Some(bonus) =
+                            Bonus::from_button(&get_ui_name(ui_event.target, &ui_transforms))
+                        {
+                            if profile.buy(bonus, &digging.balance) {
+                                profile.store();
+                                sounds.play(Sound::EmptyBucket);
+                            } else {
+                                sounds.play(Sound::RobotCaptchaFail);
+                            }
+                        }
                         if let Some(play) = finder.find("play") {
                             if play == ui_event.target {
-                                *digging = DiggingStatus::default();
                                 return SimpleTrans::Switch(Box::new(GameplayState {
                                     assets: self.assets.clone(),
+                                    save: None,
+                                    endless: false,
+                                    mode: digging.mode.clone(),
+                                    // A daily challenge plays the same day's run again.
+                                    daily: digging.daily.map(DailyChallenge::for_day),
+                                    away_card: None,
                                 }));
                             }
                         }
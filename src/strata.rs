@@ -0,0 +1,146 @@
+use amethyst::config::Config;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How deep each generated layer is, past the configured ones in endless runs.
+pub const ENDLESS_LAYER_METERS: u32 = 25;
+
+/// A band of ground between two depths, and how it plays.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Stratum {
+    pub from: u32, // Meters, inclusive.
+    pub to: u32,   // Meters, exclusive.
+    pub hardness: f32,
+    pub drill_efficiency: f32,
+    pub robot_trip: f32,
+    pub sprite: usize,
+    pub tint: (f32, f32, f32, f32),
+    #[serde(default)]
+    pub rock_chance: f32, // Chance of any one block being rock, once the pickaxe is unlocked.
+}
+
+/// The layers of the shaft, top to bottom, loaded from `config/strata.ron`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Strata {
+    pub layers: Vec<Stratum>,
+}
+
+impl Default for Strata {
+    fn default() -> Self {
+        let stratum =
+            |from, to, hardness, drill_efficiency, robot_trip, sprite, rock_chance| Stratum {
+                from,
+                to,
+                hardness,
+                drill_efficiency,
+                robot_trip,
+                sprite,
+                tint: (1., 1., 1., 1.),
+                rock_chance,
+            };
+        Strata {
+            layers: vec![
+                stratum(0, 20, 1., 1., 1., 0, 0.08),
+                stratum(20, 40, 1.25, 1., 1.1, 16, 0.12),
+                stratum(40, 99, 1.5, 1.1, 1.25, 24, 0.15),
+                stratum(99, 100, 2., 1.25, 1.5, 32, 0.),
+            ],
+        }
+    }
+}
+
+impl Strata {
+    pub fn load(path: &Path) -> Result<Strata, String> {
+        <Strata as Config>::load(path).map_err(|err| err.to_string())
+    }
+
+    /// The layer `meter` falls in. Anything past the last layer counts as the last layer.
+    pub fn at(&self, meter: u32) -> &Stratum {
+        self.layers
+            .iter()
+            .find(|layer| meter < layer.to)
+            .or_else(|| self.layers.last())
+            .expect("Unreachable: validated strata have layers")
+    }
+
+    /// The layer `meter` falls in, for an endless run. Past the configured layers, each
+    /// `ENDLESS_LAYER_METERS` gets its own layer generated from `seed`, harder and rockier the
+    /// deeper it is.
+    pub fn endless_at(&self, meter: u32, seed: u64) -> Stratum {
+        let last = self
+            .layers
+            .last()
+            .expect("Unreachable: validated strata have layers");
+        if meter < last.to {
+            return *self.at(meter);
+        }
+        let index = (meter - last.to) / ENDLESS_LAYER_METERS;
+        let roll = |salt: u64| crate::rng::hash_roll(!seed, index as u64 * 8 + salt) as f32;
+        let deeper = (index + 1) as f32;
+        let looks_like = (roll(0) * self.layers.len() as f32) as usize % self.layers.len();
+        Stratum {
+            from: last.to + index * ENDLESS_LAYER_METERS,
+            to: last.to + (index + 1) * ENDLESS_LAYER_METERS,
+            hardness: last.hardness * (1. + 0.1 * deeper) * (0.9 + 0.2 * roll(1)),
+            drill_efficiency: last.drill_efficiency * (0.9 + 0.2 * roll(2)),
+            robot_trip: last.robot_trip * (1. + 0.05 * deeper),
+            sprite: self.layers[looks_like].sprite,
+            tint: (
+                0.75 + 0.25 * roll(3),
+                0.75 + 0.25 * roll(4),
+                0.75 + 0.25 * roll(5),
+                1.,
+            ),
+            rock_chance: (0.1 + 0.02 * deeper + 0.05 * roll(6)).min(0.5),
+        }
+    }
+
+    /// The same layers with every boundary moved `scale` times as deep, for game modes that
+    /// change the victory depth.
+    pub fn stretched(&self, scale: f32) -> Strata {
+        let mut from = 0;
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| {
+                let to = ((layer.to as f32 * scale).round() as u32).max(from + 1);
+                let stretched = Stratum { from, to, ..*layer };
+                from = to;
+                stretched
+            })
+            .collect();
+        Strata { layers }
+    }
+
+    pub fn validate(&self, victory_depth: u32) -> Result<(), String> {
+        let mut from = 0;
+        for layer in self.layers.iter() {
+            if layer.from != from || layer.to <= layer.from {
+                return Err(format!(
+                    "The layer from {}m to {}m should start at {}m and be at least a meter deep",
+                    layer.from, layer.to, from
+                ));
+            }
+            if !(layer.hardness > 0. && layer.drill_efficiency > 0. && layer.robot_trip > 0.) {
+                return Err(format!(
+                    "The layer from {}m must have a positive hardness, drill_efficiency and robot_trip",
+                    layer.from
+                ));
+            }
+            if !(0. ..=1.).contains(&layer.rock_chance) {
+                return Err(format!(
+                    "The layer from {}m must have a rock_chance between 0 and 1",
+                    layer.from
+                ));
+            }
+            from = layer.to;
+        }
+        if from < victory_depth {
+            return Err(format!(
+                "The strata stop at {}m, short of victory_depth ({})",
+                from, victory_depth
+            ));
+        }
+        Ok(())
+    }
+}
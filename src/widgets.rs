@@ -1,6 +1,6 @@
 use crate::prelude::*;
 use amethyst::ui::{ToNativeWidget, UiCreator, UiWidget};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize)]
 pub enum DiggingUi {
@@ -21,7 +21,7 @@ impl ToNativeWidget for DiggingUi {
     }
 }
 
-#[derive(Component, Debug, Clone, Copy)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[storage(DenseVecStorage)]
 pub struct Position {
     pub x: f32,
@@ -35,7 +35,7 @@ pub struct WidgetSpawner<'a> {
 }
 
 impl<'a> WidgetSpawner<'a> {
-    pub fn spawn_ui_widget(&mut self, path: &'static str, position: Position) -> Entity {
+    pub fn spawn_ui_widget(&mut self, path: &str, position: Position) -> Entity {
         let entity = self.creator.create(path, ());
         self.positions
             .insert(entity, position)